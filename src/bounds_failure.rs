@@ -0,0 +1,182 @@
+use crate::prelude::*;
+
+use crate::regex::*;
+use crate::types::{Package, Tag, VersionedPackage};
+
+/// A bounds block entry generated by `add`, e.g.
+///
+/// ```text
+/// - foo < 0 # tried foo-1.0, but its *library* requires bar ==1.* and the snapshot contains bar-2.0
+/// ```
+///
+/// Every command reading or writing these lines goes through this type,
+/// so `parse` and the `Display` instance must round trip.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BoundsFailure {
+    pub(crate) package: Package,
+    /// `< 0`, only used in the lib/exe block.
+    pub(crate) disabled: bool,
+    pub(crate) tried: VersionedPackage,
+    /// One or more components as printed by curator, e.g. "library" or
+    /// "library, test-suite".
+    pub(crate) component: String,
+    pub(crate) cause: Cause,
+    pub(crate) tag: Option<Tag>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Cause {
+    /// "requires bar ==1.* and the snapshot contains bar-2.0", written
+    /// since 0.4.1.
+    OutOfBounds {
+        dependency: Package,
+        bound: String,
+        snapshot: VersionedPackage,
+    },
+    /// "does not support: bar-2.0", written before 0.4.1.
+    DoesNotSupport(VersionedPackage),
+    /// "requires the disabled package: bar"
+    DisabledPackage(Package),
+}
+
+impl Cause {
+    /// The version in the snapshot that the package can't use, if any.
+    pub(crate) fn snapshot_version(&self) -> Option<&VersionedPackage> {
+        match self {
+            Cause::OutOfBounds { snapshot, .. } | Cause::DoesNotSupport(snapshot) => Some(snapshot),
+            Cause::DisabledPackage(_) => None,
+        }
+    }
+
+    fn parse(s: &str) -> Option<Cause> {
+        if let Ok(cap) = Captures::new(
+            regex!(r#"^requires ([^ ]+) (.+) and the snapshot contains ([^ ]+)$"#),
+            s,
+        ) {
+            Some(Cause::OutOfBounds {
+                dependency: cap.get(1).unwrap(),
+                bound: cap.get(2).unwrap(),
+                snapshot: versioned(&cap, 3)?,
+            })
+        } else if let Ok(cap) = Captures::new(regex!(r#"^does not support: ([^ ]+)$"#), s) {
+            Some(Cause::DoesNotSupport(versioned(&cap, 1)?))
+        } else if let Ok(cap) =
+            Captures::new(regex!(r#"^requires the disabled package: ([^ ]+)$"#), s)
+        {
+            Some(Cause::DisabledPackage(cap.get(1).unwrap()))
+        } else {
+            None
+        }
+    }
+}
+
+fn versioned(cap: &Captures, n: usize) -> Option<VersionedPackage> {
+    VersionedPackage::try_from(cap.get::<String>(n).unwrap()).ok()
+}
+
+impl fmt::Display for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cause::OutOfBounds {
+                dependency,
+                bound,
+                snapshot,
+            } => write!(
+                f,
+                "requires {dependency} {bound} and the snapshot contains {snapshot}"
+            ),
+            Cause::DoesNotSupport(snapshot) => write!(f, "does not support: {snapshot}"),
+            Cause::DisabledPackage(package) => {
+                write!(f, "requires the disabled package: {package}")
+            }
+        }
+    }
+}
+
+impl BoundsFailure {
+    /// Parses a line of a bounds block, ignoring indentation. Returns
+    /// `None` for manually written lines.
+    pub(crate) fn parse(s: &str) -> Option<BoundsFailure> {
+        let (s, tag) = Tag::split(s);
+        let cap = Captures::new(
+            regex!(r#"^\s*- *([^ ]+)( *< *0)? *# *tried ([^ ]+), but its \*([^*]+)\* (.+?)\s*$"#),
+            s,
+        )
+        .ok()?;
+        Some(BoundsFailure {
+            package: cap.get(1).unwrap(),
+            disabled: cap.get::<&str>(2).is_ok(),
+            tried: versioned(&cap, 3)?,
+            component: cap.get(4).unwrap(),
+            cause: Cause::parse(&cap.get::<String>(5).unwrap())?,
+            tag,
+        })
+    }
+}
+
+impl fmt::Display for BoundsFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let BoundsFailure {
+            package,
+            disabled,
+            tried,
+            component,
+            cause,
+            tag,
+        } = self;
+        let lt0 = if *disabled { " < 0" } else { "" };
+        write!(
+            f,
+            "- {package}{lt0} # tried {tried}, but its *{component}* {cause}"
+        )?;
+        if let Some(tag) = tag {
+            write!(f, " {tag}")?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_parse() {
+    let s = "- b9 < 0 # tried b9-3.2.0, but its *library* requires aeson ==1.4.* and the snapshot contains aeson-2.0.3.0";
+    let failure = BoundsFailure::parse(&format!("        {s}")).unwrap();
+    assert_eq!(
+        failure,
+        BoundsFailure {
+            package: "b9".into(),
+            disabled: true,
+            tried: VersionedPackage::try_from("b9-3.2.0".to_owned()).unwrap(),
+            component: "library".to_owned(),
+            cause: Cause::OutOfBounds {
+                dependency: "aeson".into(),
+                bound: "==1.4.*".to_owned(),
+                snapshot: VersionedPackage::try_from("aeson-2.0.3.0".to_owned()).unwrap(),
+            },
+            tag: None,
+        }
+    );
+    assert_eq!(failure.to_string(), s);
+
+    for s in [
+        "- ENIG # tried ENIG-0.0.1.0, but its *test-suite* requires the disabled package: test-framework-th",
+        "- a < 0 # tried a-1, but its *library, sub-library x* does not support: base-5 [nightly-2023-01-01 2023-01-02]",
+        "- a # tried a-1, but its *benchmarks* requires b >=1 && <1.5 || ==2.* and the snapshot contains b-3 [2023-01-02]",
+    ] {
+        assert_eq!(BoundsFailure::parse(s).unwrap().to_string(), s);
+    }
+
+    assert_eq!(BoundsFailure::parse("- mmorph < 1.2"), None);
+    assert_eq!(BoundsFailure::parse("- foo < 0 # tried foo-1"), None);
+    assert_eq!(BoundsFailure::parse("- foo < 0 # 1.2.3"), None);
+}
+
+#[test]
+fn test_round_trip_build_constraints() {
+    let lines = crate::sections::read(Path::new("test/build-constraints.yaml"));
+    let generated: Vec<&String> = lines.iter().filter(|l| l.contains("# tried")).collect();
+    assert!(!generated.is_empty());
+    for line in generated {
+        let failure = BoundsFailure::parse(line).unwrap_or_else(|| panic!("{line}"));
+        assert_eq!(line.trim_start(), failure.to_string());
+    }
+}
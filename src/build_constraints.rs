@@ -37,21 +37,146 @@ impl fmt::Display for Maintenance {
     }
 }
 
+/// A maintainer section header such as
+/// `Adam Bergmark <adam@bergmark.nl> @bergmark`.
 #[derive(PartialOrd, Ord, PartialEq, Eq, Debug, Clone)]
-pub struct Maintainer(pub String);
+pub struct Maintainer {
+    pub header: String,
+    pub name: String,
+    pub emails: Vec<String>,
+    pub handles: Vec<String>,
+}
 
 impl fmt::Display for Maintainer {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.0.fmt(f)
+        self.header.fmt(f)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum HeaderProblem {
+    MissingHandle,
+    MissingSpaceBeforeEmail,
+    UnclosedEmail,
+    UnbracketedEmail(String),
+    InvalidEmail(String),
+    DoubledSpaces,
+    SurroundingWhitespace,
+}
+
+impl fmt::Display for HeaderProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeaderProblem::MissingHandle => write!(f, "Missing github handle"),
+            HeaderProblem::MissingSpaceBeforeEmail => write!(f, "Missing space before <"),
+            HeaderProblem::UnclosedEmail => write!(f, "Unbalanced < >"),
+            HeaderProblem::UnbracketedEmail(e) => write!(f, "Email {e} is not enclosed in < >"),
+            HeaderProblem::InvalidEmail(e) => write!(f, "Invalid email {e}"),
+            HeaderProblem::DoubledSpaces => write!(f, "Doubled spaces"),
+            HeaderProblem::SurroundingWhitespace => write!(f, "Leading or trailing whitespace"),
+        }
     }
 }
 
 impl Maintainer {
+    pub fn parse(header: &str) -> Maintainer {
+        let emails: Vec<String> = regex!(r#"[^\s<>@]+@[^\s<>]+"#)
+            .find_iter(header)
+            .map(|m| m.as_str().to_owned())
+            .collect();
+        let handles: Vec<String> = regex!(r#"(?:^|[\s(,])(@[^\s,)]+)"#)
+            .captures_iter(header)
+            .map(|c| c[1].to_owned())
+            .collect();
+        let name = regex!(r#"<?[^\s<>@]+@[^\s<>]+>?|(?:^|\s)@[^\s,)]+"#).replace_all(header, " ");
+        let name = regex!(" +")
+            .split(name.trim())
+            .collect::<Vec<_>>()
+            .join(" ");
+        Maintainer {
+            header: header.to_owned(),
+            name,
+            emails,
+            handles,
+        }
+    }
+
     pub fn github_users(&self) -> impl Iterator<Item = &str> {
-        regex!(" +")
-            .split(&self.0)
-            .filter(|s| regex!("^@[^ ]+$").is_match(s))
+        self.handles.iter().map(|h| h.as_str())
+    }
+
+    /// Headers listing more than one person, e.g. joined with `&`.
+    pub fn is_single_person(&self) -> bool {
+        self.handles.len() <= 1 && self.emails.len() <= 1 && !self.header.contains('&')
     }
+
+    pub fn problems(&self) -> Vec<HeaderProblem> {
+        let h = &self.header;
+        let mut problems = vec![];
+        if self.github_users().next().is_none() {
+            problems.push(HeaderProblem::MissingHandle);
+        }
+        if regex!(r#"\S<"#).is_match(h) {
+            problems.push(HeaderProblem::MissingSpaceBeforeEmail);
+        }
+        if h.matches('<').count() != h.matches('>').count() {
+            problems.push(HeaderProblem::UnclosedEmail);
+        }
+        for email in &self.emails {
+            if !h.contains(&format!("<{email}>")) && !h.contains(&format!("<{email}")) {
+                problems.push(HeaderProblem::UnbracketedEmail(email.clone()));
+            }
+            if !regex!(r#"^[^@\s<>]+@[^@\s<>.]+(\.[^@\s<>.]+)+$"#).is_match(email) {
+                problems.push(HeaderProblem::InvalidEmail(email.clone()));
+            }
+        }
+        if h.contains("  ") {
+            problems.push(HeaderProblem::DoubledSpaces);
+        }
+        if h.trim() != h {
+            problems.push(HeaderProblem::SurroundingWhitespace);
+        }
+        problems
+    }
+}
+
+#[test]
+fn test_parse_maintainer() {
+    let m = Maintainer::parse("Liang-Ting Chen<liang.ting.chen.tw@gmail.com> @L-TChen");
+    assert_eq!(m.name, "Liang-Ting Chen");
+    assert_eq!(m.emails, vec!["liang.ting.chen.tw@gmail.com"]);
+    assert_eq!(m.handles, vec!["@L-TChen"]);
+    assert_eq!(m.problems(), vec![HeaderProblem::MissingSpaceBeforeEmail]);
+
+    let m = Maintainer::parse("Michael Snoyman michael@snoyman.com @snoyberg");
+    assert_eq!(m.name, "Michael Snoyman");
+    assert_eq!(
+        m.problems(),
+        vec![HeaderProblem::UnbracketedEmail(
+            "michael@snoyman.com".to_owned()
+        )]
+    );
+
+    let m = Maintainer::parse("Fraser Murray <fraser.m.murray@gmail.com @intolerable");
+    assert_eq!(m.emails, vec!["fraser.m.murray@gmail.com"]);
+    assert_eq!(m.handles, vec!["@intolerable"]);
+    assert_eq!(m.problems(), vec![HeaderProblem::UnclosedEmail]);
+
+    let m = Maintainer::parse(
+        "Jasper Van der Jeugt @jaspervdj & Alexander Batischev <eual.jp@gmail.com> @Minoru",
+    );
+    assert_eq!(m.name, "Jasper Van der Jeugt & Alexander Batischev");
+    assert!(!m.is_single_person());
+
+    let m = Maintainer::parse("Diogo  Biazus <diogo@biazus>");
+    assert_eq!(
+        m.problems(),
+        vec![
+            HeaderProblem::MissingHandle,
+            HeaderProblem::InvalidEmail("diogo@biazus".to_owned()),
+            HeaderProblem::DoubledSpaces,
+        ]
+    );
 }
 
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -104,6 +229,10 @@ impl<'de> serde::Deserialize<'de> for BCPackage {
 pub struct BuildConstraints {
     pub ghc_version: String,
     pub packages: BTreeMap<Maintenance, Vec<BCPackage>>,
+    pub skipped_tests: Vec<BCPackage>,
+    pub expected_test_failures: Vec<BCPackage>,
+    pub skipped_benchmarks: Vec<BCPackage>,
+    pub expected_benchmark_failures: Vec<BCPackage>,
 }
 
 pub struct BCPackage2 {
@@ -127,7 +256,6 @@ impl BCPackage2 {
 }
 
 pub struct BuildConstraintsByPackage {
-    pub ghc_version: String,
     pub packages: BTreeMap<Package, BCPackage2>,
 }
 
@@ -143,10 +271,7 @@ impl BuildConstraints {
     }
 
     pub fn by_package(self) -> BuildConstraintsByPackage {
-        let BuildConstraints {
-            ghc_version,
-            packages,
-        } = self;
+        let BuildConstraints { packages, .. } = self;
         let mut packages2: BTreeMap<Package, BCPackage2> = BTreeMap::new();
         for (maintainer, packages) in packages {
             for BCPackage { package, bound } in packages {
@@ -157,12 +282,23 @@ impl BuildConstraints {
             }
         }
         BuildConstraintsByPackage {
-            ghc_version,
             packages: packages2,
         }
     }
 }
 
+/// Sections under `packages:` that are not maintainer sections.
+pub const SPECIAL_SECTIONS: [&str; 8] = [
+    "Grandfathered dependencies",
+    "Abandoned packages",
+    "Unmaintained packages with compilation failures",
+    "Removed packages",
+    "GHC upper bounds",
+    "Compilation failures",
+    "Library and exe bounds failures",
+    "Stackage upper bounds",
+];
+
 pub fn parse(f: &Path) -> BuildConstraints {
     use crate::yaml;
 
@@ -171,31 +307,32 @@ pub fn parse(f: &Path) -> BuildConstraints {
         #[serde(rename = "ghc-version")]
         ghc_version: String,
         packages: BTreeMap<String, Vec<BCPackage>>,
+        #[serde(rename = "skipped-tests", default)]
+        skipped_tests: Vec<BCPackage>,
+        #[serde(rename = "expected-test-failures", default)]
+        expected_test_failures: Vec<BCPackage>,
+        #[serde(rename = "skipped-benchmarks", default)]
+        skipped_benchmarks: Vec<BCPackage>,
+        #[serde(rename = "expected-benchmark-failures", default)]
+        expected_benchmark_failures: Vec<BCPackage>,
     }
 
     let BuildConstraintsYaml {
         ghc_version,
         packages,
+        skipped_tests,
+        expected_test_failures,
+        skipped_benchmarks,
+        expected_benchmark_failures,
     } = yaml::yaml_from_file(f)
         .unwrap_or_else(|e| panic!("Could not open build-constraints file at {f:?}, error: {e}"));
     let packages = packages
         .into_iter()
         .map(|(k, v)| {
-            let maintainer = if [
-                "Grandfathered dependencies",
-                "Abandoned packages",
-                "Unmaintained packages with compilation failures",
-                "Removed packages",
-                "GHC upper bounds",
-                "Compilation failures",
-                "Library and exe bounds failures",
-                "Stackage upper bounds",
-            ]
-            .contains(&&*k)
-            {
+            let maintainer = if SPECIAL_SECTIONS.contains(&&*k) {
                 Maintenance::Other(k)
             } else {
-                Maintenance::Maintainer(Maintainer(k))
+                Maintenance::Maintainer(Maintainer::parse(&k))
             };
             (maintainer, v)
         })
@@ -203,6 +340,10 @@ pub fn parse(f: &Path) -> BuildConstraints {
     BuildConstraints {
         ghc_version,
         packages,
+        skipped_tests,
+        expected_test_failures,
+        skipped_benchmarks,
+        expected_benchmark_failures,
     }
 }
 
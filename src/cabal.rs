@@ -0,0 +1,64 @@
+use crate::prelude::*;
+
+use crate::types::Package;
+
+/// Collects the package names from all `build-depends` fields of a
+/// .cabal file, regardless of component or conditional.
+pub fn build_depends(cabal: &str) -> BTreeSet<Package> {
+    let mut deps = BTreeSet::new();
+    let mut field: Option<(usize, String)> = None;
+    for line in cabal.lines() {
+        let indent = line.len() - line.trim_start().len();
+        if let Some((field_indent, value)) = &mut field {
+            if line.trim().is_empty() || line.trim_start().starts_with("--") {
+                continue;
+            } else if indent > *field_indent {
+                value.push(' ');
+                value.push_str(line.trim());
+                continue;
+            } else {
+                parse_depends(value, &mut deps);
+                field = None;
+            }
+        }
+        if let Some(caps) = regex!(r#"(?i)^\s*build-depends\s*:(.*)$"#).captures(line) {
+            field = Some((indent, caps[1].to_owned()));
+        }
+    }
+    if let Some((_, value)) = field {
+        parse_depends(&value, &mut deps);
+    }
+    deps
+}
+
+fn parse_depends(value: &str, deps: &mut BTreeSet<Package>) {
+    for dep in value.split(',') {
+        if let Some(caps) = regex!(r#"^\s*([A-Za-z\d][A-Za-z\d-]*)"#).captures(dep) {
+            deps.insert(Package::from(&caps[1]));
+        }
+    }
+}
+
+#[test]
+fn test_build_depends() {
+    let cabal = "name: foo
+library
+  build-depends: base >=4 && <5,
+                 aeson ^>=2.0
+               , text
+  if flag(bar)
+    build-depends:
+      -- a comment
+      containers:internal,
+      mtl
+test-suite spec
+  Build-Depends: foo, hspec
+";
+    assert_eq!(
+        build_depends(cabal),
+        ["aeson", "base", "containers", "foo", "hspec", "mtl", "text"]
+            .into_iter()
+            .map(Package::from)
+            .collect()
+    );
+}
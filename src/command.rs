@@ -3,15 +3,33 @@ use crate::prelude::*;
 pub mod add;
 pub mod add_loop;
 pub mod affected;
+pub mod age;
+pub mod audit_bounds;
+pub mod bc_diff;
+pub mod blame;
+pub mod culprits;
 pub mod diff_snapshot;
 pub mod disabled;
+pub mod fmt;
 pub mod grandfather;
+pub mod issues;
+pub mod issues_index;
+pub mod lint;
+pub mod maintainer;
 pub mod maintainers;
+pub mod move_packages;
 pub mod multiple;
 pub mod outdated;
 pub mod package_info;
+pub mod released;
+pub mod site;
+pub mod stale;
+pub mod stats;
+pub mod test_bounds;
+pub mod why;
 
-use crate::handle::{handle, Location};
+use crate::handle::{handle, upper_bounds_block, Location};
+use crate::sections;
 
 pub fn clear(build_constraints: &Path) {
     handle(build_constraints, true, |loc, _lines| match loc {
@@ -19,4 +37,10 @@ pub fn clear(build_constraints: &Path) {
         Location::Lib => vec!["        []".to_owned()],
         Location::Test | Location::Bench => vec![],
     });
+
+    let mut lines = sections::read(build_constraints);
+    if let Some(range) = upper_bounds_block(&lines) {
+        lines.drain(range);
+        fs::write_lines(build_constraints, lines);
+    }
 }
@@ -1,9 +1,14 @@
 use std::io::{self, BufRead};
 
-use crate::handle::{handle, Location};
+use crate::bounds_failure::{BoundsFailure, Cause};
+use crate::command::issues;
+use crate::handle::{
+    bounds_blocks, handle, upper_bounds_block, Location, UPPER_BOUNDS_END, UPPER_BOUNDS_START,
+};
 use crate::prelude::*;
 use crate::regex::*;
-use crate::types::{Package, Version, VersionedPackage};
+use crate::sections;
+use crate::types::{Package, Tag, Version, VersionedPackage};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 enum Header {
@@ -128,13 +133,34 @@ fn parse_header_missing(s: &str) -> Option<Header> {
 
 type H = HashMap<Header, Vec<(Package, Version, Bound, String)>>;
 
-pub fn add(build_constraints: &Path) {
-    add_impl(build_constraints, || {
-        io::stdin().lock().lines().flatten().collect()
-    });
+/// With `tag` each generated comment ends with today's date and
+/// `target`, see [`Tag`]. With a `policy` dependencies that break many
+/// packages get an upper bound instead. With `issues_dir` an issue body
+/// is written for each culprit.
+pub fn add(
+    build_constraints: &Path,
+    tag: bool,
+    target: Option<String>,
+    policy: Option<Policy>,
+    issues_dir: Option<&Path>,
+) {
+    let tag = tag.then(|| Tag::today(target));
+    add_impl(
+        build_constraints,
+        tag.as_ref(),
+        policy.as_ref(),
+        issues_dir,
+        || io::stdin().lock().lines().map_while(Result::ok).collect(),
+    );
 }
 
-pub fn add_impl(build_constraints: &Path, lines: impl FnOnce() -> Vec<String>) -> usize {
+pub fn add_impl(
+    build_constraints: &Path,
+    tag: Option<&Tag>,
+    policy: Option<&Policy>,
+    issues_dir: Option<&Path>,
+    lines: impl FnOnce() -> Vec<String>,
+) -> usize {
     let mut lib_exes: H = Default::default();
     let mut tests: H = Default::default();
     let mut benches: H = Default::default();
@@ -142,6 +168,8 @@ pub fn add_impl(build_constraints: &Path, lines: impl FnOnce() -> Vec<String>) -
 
     // Ignore everything until the bounds issues show up.
     let mut process_line = false;
+    // Reported at the end instead of aborting halfway through
+    let mut unhandled: Vec<String> = vec![];
 
     for line in lines() {
         if regex!(r#"^\s*$"#).is_match(&line) {
@@ -157,21 +185,100 @@ pub fn add_impl(build_constraints: &Path, lines: impl FnOnce() -> Vec<String>) -
             component,
         }) = parse_package_with_component(&line)
         {
-            let root = last_header.clone().unwrap();
-            match &*component {
-                "library" | "executable" => {
-                    insert(&mut lib_exes, root, &package, &version, &bound, &component)
+            let Some(root) = last_header.clone() else {
+                unhandled.push(format!("{line} (no preceding header)"));
+                continue;
+            };
+            // "Used by: library, test-suite" lists several components
+            let mut locations: Vec<(Location, Vec<&str>)> = vec![];
+            for component in component.split(',').map(|c| c.trim()) {
+                match component_location(component) {
+                    Some(loc) => match locations.iter_mut().find(|(l, _)| *l == loc) {
+                        Some((_, components)) => components.push(component),
+                        None => locations.push((loc, vec![component])),
+                    },
+                    None => unhandled.push(format!("{line} (unknown component {component:?})")),
+                }
+            }
+            for (loc, components) in locations {
+                let component = components.join(", ");
+                let root = root.clone();
+                match loc {
+                    Location::Lib => {
+                        insert(&mut lib_exes, root, &package, &version, &bound, &component)
+                    }
+                    Location::Test => {
+                        insert(&mut tests, root, &package, &version, &bound, &component)
+                    }
+                    Location::Bench => {
+                        insert(&mut benches, root, &package, &version, &bound, "benchmarks")
+                    }
                 }
-                "benchmark" => insert(&mut benches, root, &package, &version, &bound, "benchmarks"),
-                "test-suite" => insert(&mut tests, root, &package, &version, &bound, &component),
-                _ => panic!("Bad component: {component}"),
             }
         } else if let Some(header_versioned) = parse_header_versioned(&line) {
             last_header = Some(header_versioned);
         } else if let Some(missing) = parse_header_missing(&line) {
             last_header = Some(missing);
         } else {
-            panic!("Unhandled: {line:?}");
+            unhandled.push(line);
+        }
+    }
+
+    // Collected before the strategy removes any, since upper bounded
+    // culprits need an issue as well
+    let failures: Vec<BoundsFailure> = if issues_dir.is_some() {
+        let failures = [(&lib_exes, true), (&tests, false), (&benches, false)]
+            .into_iter()
+            .flat_map(|(h, lt0)| {
+                h.iter().flat_map(move |(header, packages)| {
+                    packages
+                        .iter()
+                        .map(move |(package, version, bound, component)| {
+                            bounds_failure(package, lt0, version, bound, component, header, tag)
+                        })
+                })
+            })
+            .collect();
+        new_failures(&sections::read(build_constraints), failures)
+    } else {
+        vec![]
+    };
+
+    let mut auto_upper_bounds = vec![];
+    if let Some(policy) = policy {
+        let mut versioned: Vec<&VersionedPackage> = lib_exes
+            .keys()
+            .chain(tests.keys())
+            .chain(benches.keys())
+            .filter_map(|h| match h {
+                Header::Versioned(v) => Some(v),
+                Header::Missing(_) => None,
+            })
+            .collect();
+        versioned.sort();
+        versioned.dedup();
+        let versioned: Vec<VersionedPackage> = versioned.into_iter().cloned().collect();
+
+        println!("\nSTRATEGY\n");
+        for v in versioned {
+            let header = Header::Versioned(v.clone());
+            let dependents: BTreeSet<Package> = [&lib_exes, &tests, &benches]
+                .iter()
+                .filter_map(|h| h.get(&header))
+                .flatten()
+                .map(|(package, ..)| package.clone())
+                .collect();
+            let (decision, reason) = policy.decide(&v.package, dependents.len());
+            match decision {
+                Decision::Disable => println!("{v}: disable dependents, {reason}"),
+                Decision::UpperBound => {
+                    println!("{v}: upper bound, {reason}");
+                    for h in [&mut lib_exes, &mut tests, &mut benches] {
+                        h.remove(&header);
+                    }
+                    auto_upper_bounds.push(upper_bound(&v, &dependents, tag));
+                }
+            }
         }
     }
 
@@ -184,9 +291,9 @@ pub fn add_impl(build_constraints: &Path, lines: impl FnOnce() -> Vec<String>) -
     }
     for (header, packages) in lib_exes {
         for (package, version, bound, component) in packages {
-            let s = printer(
-                "        ", &package, true, &version, &bound, &component, &header,
-            );
+            let failure =
+                bounds_failure(&package, true, &version, &bound, &component, &header, tag);
+            let s = format!("        {failure}");
             println!("{s}");
             auto_lib_exes.push(s);
         }
@@ -197,9 +304,9 @@ pub fn add_impl(build_constraints: &Path, lines: impl FnOnce() -> Vec<String>) -
     }
     for (header, packages) in tests {
         for (package, version, bound, component) in packages {
-            let s = printer(
-                "    ", &package, false, &version, &bound, &component, &header,
-            );
+            let failure =
+                bounds_failure(&package, false, &version, &bound, &component, &header, tag);
+            let s = format!("    {failure}");
             println!("{s}");
             auto_tests.push(s);
         }
@@ -210,14 +317,21 @@ pub fn add_impl(build_constraints: &Path, lines: impl FnOnce() -> Vec<String>) -
     }
     for (header, packages) in benches {
         for (package, version, bound, component) in packages {
-            let s = printer(
-                "    ", &package, false, &version, &bound, &component, &header,
-            );
+            let failure =
+                bounds_failure(&package, false, &version, &bound, &component, &header, tag);
+            let s = format!("    {failure}");
             println!("{s}");
             auto_benches.push(s);
         }
     }
 
+    if !auto_upper_bounds.is_empty() {
+        println!("\nUPPER BOUNDS\n");
+        for s in &auto_upper_bounds {
+            println!("{s}");
+        }
+    }
+
     let lib_exe_count = auto_lib_exes.len();
 
     println!();
@@ -228,31 +342,118 @@ pub fn add_impl(build_constraints: &Path, lines: impl FnOnce() -> Vec<String>) -
         benches = auto_benches.len(),
         build_constraints = build_constraints.as_os_str().to_str().unwrap(),
     );
-    adder(build_constraints, auto_lib_exes, auto_tests, auto_benches);
+    let upper_bounds = upper_bounds_adder(build_constraints, &auto_upper_bounds);
+    let counts = adder(build_constraints, auto_lib_exes, auto_tests, auto_benches);
+    let counts = [counts[0], counts[1], counts[2], upper_bounds];
+    println!(
+        "{new} new, {updated} updated, {present} already present",
+        new = counts.iter().map(|c| c.new).sum::<usize>(),
+        updated = counts.iter().map(|c| c.updated).sum::<usize>(),
+        present = counts.iter().map(|c| c.present).sum::<usize>(),
+    );
+    // Lines that were already present don't change the build plan, so
+    // add-loop would otherwise never terminate
+    let lib_exe_count = counts[0].new + counts[0].updated + upper_bounds.new + upper_bounds.updated;
+
+    if let Some(issues_dir) = issues_dir {
+        println!("\nISSUES\n");
+        issues::write_issues(&sections::read(build_constraints), &failures, issues_dir);
+    }
+
+    if !unhandled.is_empty() {
+        println!("\nCould not handle {} lines:", unhandled.len());
+        for line in unhandled {
+            println!("    {line}");
+        }
+    }
 
     lib_exe_count
 }
 
-fn printer(
-    indent: &str,
+/// The bounds block a component named by curator belongs to. Anything
+/// the package needs to build its library, such as sub-libraries,
+/// foreign libraries and the custom setup, disables the whole package.
+fn component_location(component: &str) -> Option<Location> {
+    // Named components are printed e.g. as "sub-library foo"
+    let kind = component.split(' ').next().unwrap_or_default();
+    match kind {
+        "library" | "executable" | "sub-library" | "foreign-library" | "custom-setup" => {
+            Some(Location::Lib)
+        }
+        "test-suite" => Some(Location::Test),
+        "benchmark" => Some(Location::Bench),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_component_location() {
+    assert_eq!(component_location("library"), Some(Location::Lib));
+    assert_eq!(component_location("sub-library foo"), Some(Location::Lib));
+    assert_eq!(component_location("foreign-library"), Some(Location::Lib));
+    assert_eq!(component_location("custom-setup"), Some(Location::Lib));
+    assert_eq!(component_location("build-tool"), None);
+    assert_eq!(component_location("test-suite"), Some(Location::Test));
+    assert_eq!(component_location("benchmark"), Some(Location::Bench));
+    assert_eq!(component_location("something"), None);
+}
+
+#[test]
+fn test_add_impl_unhandled() {
+    let dir = std::env::temp_dir().join(format!("commenter-add-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("build-constraints.yaml");
+    std::fs::copy("test/build-constraints.yaml", &path).unwrap();
+    let lines = [
+        "curator: Snapshot dependency graph contains errors:",
+        "- [ ] orphan-1.0 (==1.*). Used by: library",
+        "foo-2.0 (Stackage upper bounds) is out of bounds for:",
+        "- [ ] zzz-bar-1.0 (==1.*). A @a. Used by: library, test-suite, benchmark",
+        "- [ ] zzz-baz-1.0 (==1.*). A @a. Used by: sub-library internal",
+        "- [ ] zzz-qux-1.0 (==1.*). A @a. Used by: something-new",
+        "Something else entirely",
+    ];
+    let count = add_impl(&path, None, None, None, || {
+        lines.iter().map(|l| l.to_string()).collect()
+    });
+    assert_eq!(count, 2);
+    let written = sections::read(&path);
+    let has = |s: &str| written.iter().any(|l| l == s);
+    assert!(has("        - zzz-bar < 0 # tried zzz-bar-1.0, but its *library* requires foo ==1.* and the snapshot contains foo-2.0"));
+    assert!(has("    - zzz-bar # tried zzz-bar-1.0, but its *test-suite* requires foo ==1.* and the snapshot contains foo-2.0"));
+    assert!(has("    - zzz-bar # tried zzz-bar-1.0, but its *benchmarks* requires foo ==1.* and the snapshot contains foo-2.0"));
+    assert!(has("        - zzz-baz < 0 # tried zzz-baz-1.0, but its *sub-library internal* requires foo ==1.* and the snapshot contains foo-2.0"));
+    assert!(!written.iter().any(|l| l.contains("zzz-qux")));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+fn bounds_failure(
     package: &Package,
     lt0: bool,
     version: &Version,
     bound: &Bound,
     component: &str,
     header: &Header,
-) -> String {
-    let lt0 = if lt0 { " < 0" } else { "" };
-    format!(
-        "{indent}- {package}{lt0} # tried {package}-{version}, but its *{component}* {cause}",
-        cause = match header {
-            Header::Versioned(versioned) => format!(
-                "requires {package} {bound} and the snapshot contains {versioned}",
-                package = versioned.package
-            ),
-            Header::Missing(package) => format!("requires the disabled package: {package}"),
+    tag: Option<&Tag>,
+) -> BoundsFailure {
+    BoundsFailure {
+        package: package.clone(),
+        disabled: lt0,
+        tried: VersionedPackage {
+            package: package.clone(),
+            version: version.clone(),
         },
-    )
+        component: component.to_owned(),
+        cause: match header {
+            Header::Versioned(versioned) => Cause::OutOfBounds {
+                dependency: versioned.package.clone(),
+                bound: bound.to_string(),
+                snapshot: versioned.clone(),
+            },
+            Header::Missing(package) => Cause::DisabledPackage(package.clone()),
+        },
+        tag: tag.cloned(),
+    }
 }
 
 fn insert(
@@ -271,14 +472,362 @@ fn insert(
     ));
 }
 
-fn adder(build_constraints: &Path, lib: Vec<String>, test: Vec<String>, bench: Vec<String>) {
-    handle(build_constraints, true, |loc, mut lines| {
-        lines.extend(match loc {
-            Location::Lib => lib.clone(),
-            Location::Test => test.clone(),
-            Location::Bench => bench.clone(),
-        });
-        lines.sort();
+/// The failures that are neither in a bounds block nor upper bounded in
+/// the generated block of "Stackage upper bounds" yet, ignoring tags.
+fn new_failures(lines: &[String], failures: Vec<BoundsFailure>) -> Vec<BoundsFailure> {
+    let untagged = |f: &BoundsFailure| BoundsFailure {
+        tag: None,
+        ..f.clone()
+    };
+    let existing: Vec<BoundsFailure> = bounds_blocks(lines)
+        .into_iter()
+        .flat_map(|(_, range)| lines[range].iter().filter_map(|l| BoundsFailure::parse(l)))
+        .map(|f| untagged(&f))
+        .collect();
+    let upper_bounded: BTreeSet<(Package, String)> = upper_bounds_block(lines)
+        .map(|range| {
+            lines[range]
+                .iter()
+                .filter_map(|l| sections::split_entry(l))
+                .map(|(package, bound, _)| (package, bound))
+                .collect()
+        })
+        .unwrap_or_default();
+    failures
+        .into_iter()
+        .filter(|f| !existing.contains(&untagged(f)))
+        .filter(|f| {
+            f.cause.snapshot_version().is_none_or(|v| {
+                !upper_bounded.contains(&(v.package.clone(), format!("< {}", v.version)))
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn test_new_failures() {
+    let lines: Vec<String> = [
+        "packages:",
+        "    \"Stackage upper bounds\":",
+        UPPER_BOUNDS_START,
+        "        - aeson < 2.1 # aeson-2.1 is not supported by 4 packages: a, b, c and 1 more",
+        UPPER_BOUNDS_END,
+        "",
+        "    \"Library and exe bounds failures\":",
+        "        - a < 0 # tried a-1, but its *library* requires the disabled package: x [2023-01-02]",
+        "    # End of Library and exe bounds failures",
+        "# end of packages",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let failures: Vec<BoundsFailure> = [
+        "- a < 0 # tried a-1, but its *library* requires the disabled package: x",
+        "- b < 0 # tried b-1, but its *library* requires the disabled package: x",
+        "- c < 0 # tried c-1, but its *library* requires aeson <2.1 and the snapshot contains aeson-2.1",
+        "- c # tried c-1, but its *test-suite* requires text <2 and the snapshot contains text-2.0",
+    ]
+    .iter()
+    .map(|s| BoundsFailure::parse(s).unwrap())
+    .collect();
+    let new: Vec<String> = new_failures(&lines, failures)
+        .iter()
+        .map(|f| f.package.to_string())
+        .collect();
+    assert_eq!(new, vec!["b", "c"]);
+}
+
+/// New, updated and already present lines for each bounds block, in
+/// the order lib, test, bench.
+fn adder(
+    build_constraints: &Path,
+    lib: Vec<String>,
+    test: Vec<String>,
+    bench: Vec<String>,
+) -> [Counts; 3] {
+    let mut counts = [Counts::default(); 3];
+    handle(build_constraints, true, |loc, lines| {
+        let (new, counts) = match loc {
+            Location::Lib => (&lib, &mut counts[0]),
+            Location::Test => (&test, &mut counts[1]),
+            Location::Bench => (&bench, &mut counts[2]),
+        };
+        let mut lines = merge(lines, new, counts, merge_key);
+        sections::sort_entries(&mut lines, str::to_owned);
         lines
     });
+    counts
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Counts {
+    new: usize,
+    updated: usize,
+    present: usize,
+}
+
+/// The identity of a generated line, ignoring the tried version and tag.
+fn merge_key(line: &str) -> Option<(Package, String, Cause)> {
+    let BoundsFailure {
+        package,
+        component,
+        cause,
+        ..
+    } = BoundsFailure::parse(line)?;
+    Some((package, component, cause))
+}
+
+/// Adds `new` to the lines of a bounds block. A line for the same
+/// package, component and cause replaces the existing one rather than
+/// being added next to it. Lines that only differ in their tag are kept
+/// as is, so the tag records when the failure was first seen.
+fn merge<K: PartialEq>(
+    mut lines: Vec<String>,
+    new: &[String],
+    counts: &mut Counts,
+    key: impl Fn(&str) -> Option<K>,
+) -> Vec<String> {
+    for line in new {
+        let untagged = Tag::split(line).0;
+        if lines.iter().any(|l| Tag::split(l).0 == untagged) {
+            counts.present += 1;
+            continue;
+        }
+        let existing =
+            key(line).and_then(|k| lines.iter().position(|l| key(l).as_ref() == Some(&k)));
+        match existing {
+            Some(i) => {
+                lines[i] = line.clone();
+                counts.updated += 1;
+            }
+            None => {
+                lines.push(line.clone());
+                counts.new += 1;
+            }
+        }
+    }
+    lines
+}
+
+#[test]
+fn test_merge() {
+    let lines = |s: &[&str]| -> Vec<String> { s.iter().map(|l| l.to_string()).collect() };
+    let existing = lines(&[
+        "        - a < 0 # tried a-1, but its *library* requires the disabled package: x",
+        "        - b < 0 # tried b-1, but its *library* requires the disabled package: x",
+    ]);
+    let new = lines(&[
+        "        - a < 0 # tried a-1, but its *library* requires the disabled package: x",
+        "        - b < 0 # tried b-2, but its *library* requires the disabled package: x",
+        "        - b < 0 # tried b-2, but its *library* requires the disabled package: y",
+        "        - c < 0 # tried c-1, but its *library* requires the disabled package: x",
+    ]);
+    let mut counts = Counts::default();
+    let merged = merge(existing, &new, &mut counts, merge_key);
+    assert_eq!(
+        counts,
+        Counts {
+            new: 2,
+            updated: 1,
+            present: 1
+        }
+    );
+    assert_eq!(merged, new);
+
+    let mut counts = Counts::default();
+    assert_eq!(merge(merged, &new, &mut counts, merge_key), new);
+    assert_eq!(counts.present, 4);
+
+    // A later run keeps the date the failure was first seen
+    let tagged = lines(&[
+        "        - a < 0 # tried a-1, but its *library* requires the disabled package: x [nightly-2023-01-01 2023-01-01]",
+    ]);
+    let retagged = lines(&[
+        "        - a < 0 # tried a-1, but its *library* requires the disabled package: x [nightly-2023-02-01 2023-02-01]",
+    ]);
+    let mut counts = Counts::default();
+    assert_eq!(
+        merge(tagged.clone(), &retagged, &mut counts, merge_key),
+        tagged
+    );
+    assert_eq!(counts.present, 1);
+}
+
+/// When to add an upper bound on a dependency to "Stackage upper bounds"
+/// instead of disabling everything that does not support its new version.
+pub struct Policy {
+    /// Upper bound dependencies with at least this many dependents.
+    pub threshold: usize,
+    /// Dependencies that are always upper bounded.
+    pub allow: Vec<String>,
+    /// Dependencies that are never upper bounded.
+    pub deny: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Decision {
+    Disable,
+    UpperBound,
+}
+
+impl Policy {
+    /// The decision together with the reasoning for it.
+    fn decide(&self, dependency: &Package, dependents: usize) -> (Decision, String) {
+        let listed = |list: &[String]| list.iter().any(|p| p == dependency.as_ref());
+        if listed(&self.deny) {
+            (Decision::Disable, format!("{dependency} is denied"))
+        } else if listed(&self.allow) {
+            (Decision::UpperBound, format!("{dependency} is allowed"))
+        } else if dependents >= self.threshold {
+            (
+                Decision::UpperBound,
+                format!("{dependents} dependents >= {}", self.threshold),
+            )
+        } else {
+            (
+                Decision::Disable,
+                format!("{dependents} dependents < {}", self.threshold),
+            )
+        }
+    }
+}
+
+#[test]
+fn test_decide() {
+    let policy = Policy {
+        threshold: 3,
+        allow: vec!["text".to_owned()],
+        deny: vec!["base".to_owned()],
+    };
+    let decide = |p: &str, n| policy.decide(&Package::from(p), n).0;
+    assert_eq!(decide("aeson", 2), Decision::Disable);
+    assert_eq!(decide("aeson", 3), Decision::UpperBound);
+    assert_eq!(decide("text", 1), Decision::UpperBound);
+    assert_eq!(decide("base", 10), Decision::Disable);
+}
+
+fn upper_bound(v: &VersionedPackage, dependents: &BTreeSet<Package>, tag: Option<&Tag>) -> String {
+    let max = 3;
+    let mut names = dependents
+        .iter()
+        .take(max)
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    if dependents.len() > max {
+        names = format!("{names} and {} more", dependents.len() - max);
+    }
+    let tag = tag.map(|t| format!(" {t}")).unwrap_or_default();
+    format!(
+        "        - {package} < {version} # {v} is not supported by {n} packages: {names}{tag}",
+        package = v.package,
+        version = v.version,
+        n = dependents.len(),
+    )
+}
+
+/// Merges `new` into the generated block of "Stackage upper bounds",
+/// creating the block if needed.
+fn upper_bounds_adder(build_constraints: &Path, new: &[String]) -> Counts {
+    let mut counts = Counts::default();
+    if new.is_empty() {
+        return counts;
+    }
+    let mut lines = sections::read(build_constraints);
+    add_upper_bounds(&mut lines, new, &mut counts);
+    fs::write_lines(build_constraints, lines);
+    counts
+}
+
+fn add_upper_bounds(lines: &mut Vec<String>, new: &[String], counts: &mut Counts) {
+    let range = match upper_bounds_block(lines) {
+        Some(range) => range,
+        None => {
+            let Some(section) = sections::sections(lines)
+                .into_iter()
+                .find(|s| s.name == "Stackage upper bounds")
+            else {
+                eprintln!("ERROR: Could not find the \"Stackage upper bounds\" section");
+                std::process::exit(1);
+            };
+            // The header may be `"Stackage upper bounds": []`
+            lines[section.header] = format!("    \"{}\":", section.name);
+            let start = section.header + 1;
+            lines.splice(
+                start..start,
+                [UPPER_BOUNDS_START.to_owned(), UPPER_BOUNDS_END.to_owned()],
+            );
+            start + 1..start + 1
+        }
+    };
+    let mut block = merge(lines[range.clone()].to_vec(), new, counts, |l| {
+        sections::split_entry(l).map(|(package, ..)| package)
+    });
+    sections::sort_entries(&mut block, str::to_owned);
+    lines.splice(range, block);
+}
+
+#[test]
+fn test_add_upper_bounds() {
+    let aeson = VersionedPackage {
+        package: "aeson".into(),
+        version: "2.1".try_into().unwrap(),
+    };
+    let dependents: BTreeSet<Package> = ["a", "b", "c", "d"]
+        .into_iter()
+        .map(Package::from)
+        .collect();
+    let line = upper_bound(&aeson, &dependents, None);
+    assert_eq!(
+        line,
+        "        - aeson < 2.1 # aeson-2.1 is not supported by 4 packages: a, b, c and 1 more"
+    );
+    let new = vec![line.clone()];
+
+    let mut lines: Vec<String> = [
+        "packages:",
+        "    \"Stackage upper bounds\":",
+        "        - mmorph < 1.2",
+        "# end of packages",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let mut counts = Counts::default();
+    add_upper_bounds(&mut lines, &new, &mut counts);
+    add_upper_bounds(&mut lines, &new, &mut counts);
+    assert_eq!((counts.new, counts.present), (1, 1));
+    assert_eq!(
+        lines,
+        vec![
+            "packages:",
+            "    \"Stackage upper bounds\":",
+            UPPER_BOUNDS_START,
+            &line,
+            UPPER_BOUNDS_END,
+            "        - mmorph < 1.2",
+            "# end of packages",
+        ]
+    );
+
+    let mut lines: Vec<String> = [
+        "packages:",
+        "    \"Stackage upper bounds\": []",
+        "# end of packages",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    add_upper_bounds(&mut lines, &new, &mut Counts::default());
+    assert_eq!(
+        lines,
+        vec![
+            "packages:",
+            "    \"Stackage upper bounds\":",
+            UPPER_BOUNDS_START,
+            &line,
+            UPPER_BOUNDS_END,
+            "# end of packages",
+        ]
+    );
 }
@@ -1,10 +1,17 @@
 use crate::prelude::*;
 
 use crate::build_constraints;
-use crate::command;
+use crate::command::{self, add::Policy};
 use crate::curator;
-
-pub fn add_loop(build_constraints: &Path, clear: bool, target: Option<String>) {
+use crate::types::Tag;
+
+pub fn add_loop(
+    build_constraints: &Path,
+    clear: bool,
+    target: Option<String>,
+    tag: bool,
+    policy: Option<Policy>,
+) {
     if clear {
         println!("Clearing {}", build_constraints.display());
         crate::command::clear(build_constraints);
@@ -19,6 +26,7 @@ pub fn add_loop(build_constraints: &Path, clear: bool, target: Option<String>) {
     });
 
     let no_download = target.starts_with("lts-");
+    let tag = tag.then(|| Tag::today(Some(target.clone())));
 
     {
         println!("curator update");
@@ -36,7 +44,13 @@ pub fn add_loop(build_constraints: &Path, clear: bool, target: Option<String>) {
         curator::snapshot();
 
         let lines = curator::stack(&ghc_version);
-        let lib_count = command::add::add_impl(build_constraints, move || lines);
+        let lib_count = command::add::add_impl(
+            build_constraints,
+            tag.as_ref(),
+            policy.as_ref(),
+            None,
+            move || lines,
+        );
         if lib_count == 0 {
             add = false;
         }
@@ -0,0 +1,115 @@
+use crate::prelude::*;
+
+use chrono::NaiveDate;
+
+use crate::handle::{bounds_blocks, Location};
+use crate::sections;
+use crate::types::{Package, Tag};
+
+/// Prints the generated bounds that were tagged more than `days` days
+/// ago, oldest first, so they can be retried.
+pub fn age(build_constraints: &Path, days: i64) {
+    let lines = sections::read(build_constraints);
+    let today = chrono::Utc::now().date_naive();
+    let (aged, untagged) = aged(&lines, today, days);
+
+    for Aged {
+        location,
+        package,
+        tag,
+        comment,
+    } in &aged
+    {
+        let days = (today - tag.date).num_days();
+        let target = tag.target.as_deref().unwrap_or("-");
+        println!(
+            "{date} ({days} days, {target}) {location:?}: {package} # {comment}",
+            date = tag.date,
+        );
+    }
+    println!(
+        "\n{} entries older than {days} days, {untagged} entries without a tag",
+        aged.len()
+    );
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Aged {
+    location: Location,
+    package: Package,
+    tag: Tag,
+    comment: String,
+}
+
+/// The tagged entries older than `days`, and the number of entries
+/// without a tag.
+fn aged(lines: &[String], today: NaiveDate, days: i64) -> (Vec<Aged>, usize) {
+    let mut aged = vec![];
+    let mut untagged = 0;
+    for (location, range) in bounds_blocks(lines) {
+        for line in &lines[range] {
+            let (line, tag) = Tag::split(line);
+            let Some((package, _, comment)) = sections::split_entry(line) else {
+                continue;
+            };
+            match tag {
+                None => untagged += 1,
+                Some(tag) if (today - tag.date).num_days() > days => aged.push(Aged {
+                    location,
+                    package,
+                    tag,
+                    comment,
+                }),
+                Some(_) => {}
+            }
+        }
+    }
+    aged.sort_by_key(|a| a.tag.date);
+    (aged, untagged)
+}
+
+#[test]
+fn test_aged() {
+    let lines: Vec<String> = [
+        "packages:",
+        "    \"Library and exe bounds failures\":",
+        "        - a < 0 # tried a-1, but its *library* does not support: base-5 [nightly-2023-03-01 2023-03-02]",
+        "        - b < 0 # tried b-1, but its *library* does not support: base-5 [2023-01-01]",
+        "        - c < 0 # tried c-1, but its *library* does not support: base-5",
+        "    # End of Library and exe bounds failures",
+        "# end of packages",
+        "skipped-tests:",
+        "    # Test bounds issues",
+        "    - d # tried d-1, but its *test-suite* does not support: hspec-3 [nightly-2023-03-30 2023-03-30]",
+        "    # End of Test bounds issues",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let date = |s| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+    let (aged, untagged) = aged(&lines, date("2023-04-01"), 7);
+    assert_eq!(untagged, 1);
+    assert_eq!(
+        aged,
+        vec![
+            Aged {
+                location: Location::Lib,
+                package: Package::from("b"),
+                tag: Tag {
+                    target: None,
+                    date: date("2023-01-01"),
+                },
+                comment: "tried b-1, but its *library* does not support: base-5".to_owned(),
+            },
+            Aged {
+                location: Location::Lib,
+                package: Package::from("a"),
+                tag: Tag {
+                    target: Some("nightly-2023-03-01".to_owned()),
+                    date: date("2023-03-02"),
+                },
+                comment: "tried a-1, but its *library* does not support: base-5".to_owned(),
+            },
+        ]
+    );
+}
@@ -0,0 +1,274 @@
+use crate::prelude::*;
+
+use crate::build_constraints::SPECIAL_SECTIONS;
+use crate::command::issues_index::entry_references;
+use crate::latest_version::all_versions;
+use crate::sections;
+use crate::types::{Package, Version};
+
+/// Special section whose bounds are audited alongside the maintainer
+/// sections.
+const UPPER_BOUNDS: &str = "Stackage upper bounds";
+
+/// Checks the manual bounds in maintainer sections and "Stackage upper
+/// bounds" against all versions of the package in pantry.
+pub fn audit_bounds(build_constraints: &Path) {
+    let lines = sections::read(build_constraints);
+    let bounds = bounds(&lines);
+    let versions = all_versions(bounds.iter().map(|b| &b.package));
+    let references = entry_references(&lines);
+
+    let mut excluding = vec![];
+    let mut redundant = vec![];
+    let mut lower_only = vec![];
+    let mut unknown = vec![];
+    for bound in &bounds {
+        let issues = match references.get(&bound.line) {
+            Some(refs) => format!(" ({})", refs.join(", ")),
+            None => String::new(),
+        };
+        let location = format!(
+            "{line}: {section:?}: {package} {bound}",
+            line = bound.line,
+            section = bound.section,
+            package = bound.package,
+            bound = bound.bound,
+        );
+        match audit(&bound.bound, versions.get(&bound.package)) {
+            Audit::Excludes { allowed, excluded } => {
+                let allowed = allowed.map_or("nothing".to_owned(), |v| v.to_string());
+                excluding.push(format!(
+                    "{location}: allows {allowed}, excludes {excluded} newer versions{issues}"
+                ))
+            }
+            Audit::Redundant(latest) => {
+                redundant.push(format!("{location}: latest {latest} is allowed{issues}"))
+            }
+            Audit::LowerOnly => lower_only.push(format!("{location}{issues}")),
+            Audit::Unknown(reason) => unknown.push(format!("{location}: {reason}")),
+        }
+    }
+
+    for (title, lines) in [
+        ("Bounds excluding newer releases", excluding),
+        ("Redundant bounds", redundant),
+        ("Bounds without an upper bound", lower_only),
+        ("Could not audit", unknown),
+    ] {
+        if !lines.is_empty() {
+            println!("{title}:");
+            for line in lines {
+                println!("    {line}");
+            }
+            println!();
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Bound {
+    /// 1-based
+    line: usize,
+    section: String,
+    package: Package,
+    bound: String,
+}
+
+/// Bounds other than `< 0` in the maintainer sections and "Stackage
+/// upper bounds".
+fn bounds(lines: &[String]) -> Vec<Bound> {
+    let mut res = vec![];
+    for section in sections::sections(lines) {
+        if SPECIAL_SECTIONS.contains(&&*section.name) && section.name != UPPER_BOUNDS {
+            continue;
+        }
+        for entry in &section.entries {
+            let Some((package, bound, _)) = sections::split_entry(&lines[entry.line]) else {
+                continue;
+            };
+            if bound.is_empty() || regex!(r#"^< *0$"#).is_match(&bound) {
+                continue;
+            }
+            res.push(Bound {
+                line: entry.line + 1,
+                section: section.name.clone(),
+                package,
+                bound,
+            });
+        }
+    }
+    res
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Audit {
+    /// The newest version that is allowed, and the number of newer
+    /// versions that are not.
+    Excludes {
+        allowed: Option<Version>,
+        excluded: usize,
+    },
+    /// The latest version is allowed by an upper bound.
+    Redundant(Version),
+    /// The latest version is allowed, but the bound has no upper bound
+    /// (e.g. `>= 2.0`) so it is not redundant.
+    LowerOnly,
+    Unknown(String),
+}
+
+fn audit(bound: &str, versions: Option<&BTreeSet<Version>>) -> Audit {
+    let Some(versions) = versions else {
+        return Audit::Unknown("not found in pantry".to_owned());
+    };
+    let mut allowed_versions = vec![];
+    for v in versions {
+        match allows(bound, v) {
+            Some(true) => allowed_versions.push(v),
+            Some(false) => {}
+            None => return Audit::Unknown(format!("could not parse bound {bound:?}")),
+        }
+    }
+    let latest = versions.iter().next_back().unwrap();
+    if allowed_versions.last() == Some(&latest) {
+        return if has_upper(bound) {
+            Audit::Redundant(latest.clone())
+        } else {
+            Audit::LowerOnly
+        };
+    }
+    let allowed = allowed_versions.last().cloned().cloned();
+    let excluded = versions
+        .iter()
+        .filter(|v| allowed.as_ref().is_none_or(|a| *v > a))
+        .count();
+    Audit::Excludes { allowed, excluded }
+}
+
+/// Whether `version` is in the cabal version range `bound`, `None` if
+/// the range could not be parsed.
+fn allows(bound: &str, version: &Version) -> Option<bool> {
+    let mut any = false;
+    for alternative in bound.split("||") {
+        let mut all = true;
+        for constraint in alternative.split("&&") {
+            all &= allows_constraint(constraint.trim(), version)?;
+        }
+        any |= all;
+    }
+    Some(any)
+}
+
+/// Whether every alternative of the (parseable) cabal version range
+/// `bound` has an upper bound, so that it could exclude a future release.
+fn has_upper(bound: &str) -> bool {
+    bound.split("||").all(|alternative| {
+        alternative
+            .split("&&")
+            .any(|c| regex!(r#"^(<=|==|\^>=|<)"#).is_match(c.trim()))
+    })
+}
+
+fn allows_constraint(constraint: &str, version: &Version) -> Option<bool> {
+    let cap = regex!(r#"^(<=|>=|==|\^>=|<|>) *([\d.]+)(\.\*)?$"#).captures(constraint)?;
+    let bound = Version::try_from(&cap[2]).ok()?;
+    // Versions starting with the first `n` components of the bound
+    let prefix = |n: usize| {
+        let prefix = cap[2].split('.').take(n).collect::<Vec<_>>().join(".");
+        let v = version.to_string();
+        v == prefix || v.starts_with(&format!("{prefix}."))
+    };
+    Some(match (&cap[1], cap.get(3).is_some()) {
+        ("==", true) => prefix(usize::MAX),
+        (_, true) => return None,
+        ("<", _) => version < &bound,
+        ("<=", _) => version <= &bound,
+        (">", _) => version > &bound,
+        (">=", _) => version >= &bound,
+        ("==", _) => version == &bound,
+        ("^>=", _) => version >= &bound && prefix(2),
+        _ => unreachable!(),
+    })
+}
+
+#[test]
+fn test_allows() {
+    let v = |s: &str| Version::try_from(s).unwrap();
+    assert_eq!(allows("< 1.2", &v("1.1.9")), Some(true));
+    assert_eq!(allows("< 1.2", &v("1.2")), Some(false));
+    assert_eq!(allows(">= 2.0.0.0", &v("2.1")), Some(true));
+    assert_eq!(allows("< 3.2.7 || > 3.2.7", &v("3.2.7")), Some(false));
+    assert_eq!(allows("< 3.2.7 || > 3.2.7", &v("3.2.8")), Some(true));
+    assert_eq!(allows(">= 1 && < 1.5", &v("1.5")), Some(false));
+    assert_eq!(allows("== 1.2.*", &v("1.2.3")), Some(true));
+    assert_eq!(allows("== 1.2.*", &v("1.20")), Some(false));
+    assert_eq!(allows("^>= 1.2.3", &v("1.2.4")), Some(true));
+    assert_eq!(allows("^>= 1.2.3", &v("1.3")), Some(false));
+    assert_eq!(allows("<0.7", &v("0.6")), Some(true));
+    assert_eq!(allows("< 1.2 # comment", &v("1.0")), None);
+}
+
+#[test]
+fn test_audit() {
+    let versions: BTreeSet<Version> = ["1.0", "1.1", "1.2", "1.2.1", "2.0"]
+        .into_iter()
+        .map(|v| Version::try_from(v).unwrap())
+        .collect();
+    assert_eq!(
+        audit("< 1.2", Some(&versions)),
+        Audit::Excludes {
+            allowed: Some(Version::try_from("1.1").unwrap()),
+            excluded: 3,
+        }
+    );
+    assert_eq!(
+        audit("< 3", Some(&versions)),
+        Audit::Redundant(Version::try_from("2.0").unwrap())
+    );
+    assert_eq!(
+        audit("< 1", Some(&versions)),
+        Audit::Excludes {
+            allowed: None,
+            excluded: 5,
+        }
+    );
+    assert_eq!(audit(">= 1.1", Some(&versions)), Audit::LowerOnly);
+    assert_eq!(
+        audit(">= 1.1 && < 3", Some(&versions)),
+        Audit::Redundant(Version::try_from("2.0").unwrap())
+    );
+    assert_eq!(audit("< 1.1 || > 1.1", Some(&versions)), Audit::LowerOnly);
+    assert_eq!(
+        audit("== 2.0.*", Some(&versions)),
+        Audit::Redundant(Version::try_from("2.0").unwrap())
+    );
+    assert!(matches!(audit("< 1", None), Audit::Unknown(_)));
+
+    let lines: Vec<String> = [
+        "packages:",
+        "    \"A @a\":",
+        "        - foo < 1.2 # #1",
+        "        - bar",
+        "        - aeson >= 2.0.0.0",
+        "        - baz < 0",
+        "    \"Compilation failures\":",
+        "        - qux < 2",
+        "    \"Stackage upper bounds\":",
+        "        - mmorph < 1.2",
+        "# end of packages",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let bounds: Vec<(usize, String)> = bounds(&lines)
+        .into_iter()
+        .map(|b| (b.line, b.package.to_string()))
+        .collect();
+    assert_eq!(
+        bounds,
+        vec![
+            (3, "foo".to_owned()),
+            (5, "aeson".to_owned()),
+            (10, "mmorph".to_owned())
+        ]
+    );
+}
@@ -0,0 +1,531 @@
+use crate::prelude::*;
+
+use serde::Serialize;
+
+use crate::build_constraints::SPECIAL_SECTIONS;
+use crate::command::disabled::parse_disabled_transitviely;
+use crate::git;
+use crate::sections;
+use crate::types::Package;
+
+#[derive(Debug, Clone, Copy, strum::EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Mode {
+    Text,
+    Markdown,
+    Json,
+}
+
+/// Top level lists whose changes are reported.
+const LISTS: [&str; 4] = [
+    "skipped-tests",
+    "expected-test-failures",
+    "skipped-benchmarks",
+    "expected-benchmark-failures",
+];
+
+/// `old` and `new` are paths, or `git:REV` to read `build_constraints`
+/// at that revision.
+pub fn bc_diff(build_constraints: &Path, old: &str, new: &str, mode: Mode) {
+    let read = |source: &str| match source.strip_prefix("git:") {
+        Some(rev) => git::show(rev, build_constraints),
+        None => sections::read(Path::new(source)),
+    };
+    let diff = diff(&Model::new(&read(old)), &Model::new(&read(new)));
+    match mode {
+        Mode::Text => print_text(&diff),
+        Mode::Markdown => print_markdown(&diff),
+        Mode::Json => println!("{}", serde_json::to_string_pretty(&diff).unwrap()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    bound: String,
+    comment: String,
+}
+
+struct Model {
+    /// All entries of each package, since a package can have a line per
+    /// component or culprit.
+    sections: BTreeMap<String, BTreeMap<Package, Vec<Entry>>>,
+    lists: BTreeMap<&'static str, BTreeSet<Package>>,
+}
+
+impl Model {
+    fn new(lines: &[String]) -> Model {
+        let sections = sections::sections(lines)
+            .into_iter()
+            .map(|s| {
+                let mut entries: BTreeMap<Package, Vec<Entry>> = BTreeMap::new();
+                for (package, entry) in s.entries.iter().filter_map(|e| parse_entry(&lines[e.line]))
+                {
+                    entries.entry(package).or_default().push(entry);
+                }
+                (s.name, entries)
+            })
+            .collect();
+        let lists = LISTS
+            .iter()
+            .map(|&key| {
+                let items = sections::list_items(lines, key);
+                (key, items.into_iter().map(|(_, p)| p).collect())
+            })
+            .collect();
+        Model { sections, lists }
+    }
+
+    fn disabled(&self) -> BTreeMap<&Package, BTreeSet<Cause>> {
+        let mut disabled: BTreeMap<&Package, BTreeSet<Cause>> = BTreeMap::new();
+        for (section, entries) in &self.sections {
+            for (package, entry) in entries
+                .iter()
+                .flat_map(|(p, es)| es.iter().map(move |e| (p, e)))
+            {
+                if !regex!(r#"^< *0\b"#).is_match(&entry.bound) {
+                    continue;
+                }
+                let cause = match &**section {
+                    "Compilation failures" => Cause::Compile,
+                    "Library and exe bounds failures" => {
+                        let line = format!("- {package} < 0 # {}", entry.comment);
+                        match parse_disabled_transitviely(&line) {
+                            Some(d) => Cause::Transitive {
+                                parent: d.parent.to_string(),
+                            },
+                            None => Cause::Bounds {
+                                reason: entry.comment.clone(),
+                            },
+                        }
+                    }
+                    _ => Cause::Manual {
+                        section: section.clone(),
+                    },
+                };
+                disabled.entry(package).or_default().insert(cause);
+            }
+        }
+        disabled
+    }
+
+    /// Bounds other than `< 0`, for every package listed anywhere.
+    fn bounds(&self) -> BTreeMap<&Package, BTreeSet<&str>> {
+        let mut bounds: BTreeMap<&Package, BTreeSet<&str>> = BTreeMap::new();
+        for entries in self.sections.values() {
+            for (package, entries) in entries {
+                let set = bounds.entry(package).or_default();
+                for entry in entries {
+                    if !entry.bound.is_empty() && !regex!(r#"^< *0\b"#).is_match(&entry.bound) {
+                        set.insert(&entry.bound);
+                    }
+                }
+            }
+        }
+        bounds
+    }
+}
+
+fn parse_entry(line: &str) -> Option<(Package, Entry)> {
+    let (package, bound, comment) = sections::split_entry(line)?;
+    Some((package, Entry { bound, comment }))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(tag = "cause", rename_all = "kebab-case")]
+enum Cause {
+    Bounds { reason: String },
+    Compile,
+    Transitive { parent: String },
+    Manual { section: String },
+}
+
+impl fmt::Display for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cause::Bounds { reason } => write!(f, "bounds: {reason}"),
+            Cause::Compile => write!(f, "compilation failure"),
+            Cause::Transitive { parent } => write!(f, "transitive, requires {parent}"),
+            Cause::Manual { section } => write!(f, "manual, in {section:?}"),
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+struct Changes {
+    added: Vec<String>,
+    removed: Vec<String>,
+}
+
+impl Changes {
+    fn new<'a>(
+        old: impl IntoIterator<Item = &'a Package>,
+        new: impl IntoIterator<Item = &'a Package>,
+    ) -> Changes {
+        let old: BTreeSet<&Package> = old.into_iter().collect();
+        let new: BTreeSet<&Package> = new.into_iter().collect();
+        Changes {
+            added: new.difference(&old).map(|p| p.to_string()).collect(),
+            removed: old.difference(&new).map(|p| p.to_string()).collect(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct Rename {
+    from: String,
+    to: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct BoundChange {
+    package: String,
+    old: Vec<String>,
+    new: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct Disabled {
+    package: String,
+    causes: Vec<Cause>,
+}
+
+/// A package that is disabled before and after, but for other reasons.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct CausesChange {
+    package: String,
+    old: Vec<Cause>,
+    new: Vec<Cause>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+struct BcDiff {
+    renamed_sections: Vec<Rename>,
+    added_sections: Vec<String>,
+    removed_sections: Vec<String>,
+    /// Packages added or removed per maintainer section, keyed by the
+    /// new name of the section.
+    sections: BTreeMap<String, Changes>,
+    bounds: Vec<BoundChange>,
+    disabled: Vec<Disabled>,
+    causes: Vec<CausesChange>,
+    enabled: Vec<String>,
+    lists: BTreeMap<String, Changes>,
+}
+
+fn diff(old: &Model, new: &Model) -> BcDiff {
+    let mut d = BcDiff::default();
+
+    let mut removed: Vec<&String> = old
+        .sections
+        .keys()
+        .filter(|s| !new.sections.contains_key(*s))
+        .collect();
+    let mut added: Vec<&String> = new
+        .sections
+        .keys()
+        .filter(|s| !old.sections.contains_key(*s))
+        .collect();
+    // A section is considered renamed if it has the exact same packages
+    // as a section that was added.
+    let mut renames: BTreeMap<&String, &String> = BTreeMap::new();
+    removed.retain(|from| {
+        let packages: BTreeSet<&Package> = old.sections[*from].keys().collect();
+        let to = added.iter().position(|to| {
+            !packages.is_empty() && new.sections[*to].keys().collect::<BTreeSet<_>>() == packages
+        });
+        match to {
+            Some(i) => {
+                renames.insert(added.remove(i), from);
+                false
+            }
+            None => true,
+        }
+    });
+    d.renamed_sections = renames
+        .iter()
+        .map(|(to, from)| Rename {
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+        .collect();
+    d.added_sections = added.iter().map(|s| s.to_string()).collect();
+    d.removed_sections = removed.iter().map(|s| s.to_string()).collect();
+
+    let empty = BTreeMap::new();
+    let names: BTreeSet<&String> = new.sections.keys().chain(removed.iter().copied()).collect();
+    for name in names {
+        if SPECIAL_SECTIONS.contains(&&**name) {
+            continue;
+        }
+        let old_name = renames.get(name).copied().unwrap_or(name);
+        let changes = Changes::new(
+            old.sections.get(old_name).unwrap_or(&empty).keys(),
+            new.sections.get(name).unwrap_or(&empty).keys(),
+        );
+        if !changes.is_empty() {
+            d.sections.insert(name.clone(), changes);
+        }
+    }
+
+    let (old_bounds, new_bounds) = (old.bounds(), new.bounds());
+    for (package, new_bounds) in &new_bounds {
+        if let Some(old_bounds) = old_bounds.get(package) {
+            if old_bounds != new_bounds {
+                let strings = |b: &BTreeSet<&str>| b.iter().map(|s| s.to_string()).collect();
+                d.bounds.push(BoundChange {
+                    package: package.to_string(),
+                    old: strings(old_bounds),
+                    new: strings(new_bounds),
+                });
+            }
+        }
+    }
+
+    let (old_disabled, new_disabled) = (old.disabled(), new.disabled());
+    d.disabled = new_disabled
+        .iter()
+        .filter(|(p, _)| !old_disabled.contains_key(*p))
+        .map(|(p, causes)| Disabled {
+            package: p.to_string(),
+            causes: causes.iter().cloned().collect(),
+        })
+        .collect();
+    d.causes = new_disabled
+        .iter()
+        .filter_map(|(p, new)| {
+            let old = old_disabled.get(p).filter(|old| *old != new)?;
+            Some(CausesChange {
+                package: p.to_string(),
+                old: old.iter().cloned().collect(),
+                new: new.iter().cloned().collect(),
+            })
+        })
+        .collect();
+    d.enabled = old_disabled
+        .keys()
+        .filter(|p| !new_disabled.contains_key(*p))
+        .map(|p| p.to_string())
+        .collect();
+
+    for key in LISTS {
+        let changes = Changes::new(&old.lists[key], &new.lists[key]);
+        if !changes.is_empty() {
+            d.lists.insert(key.to_owned(), changes);
+        }
+    }
+    d
+}
+
+/// The diff as a list of titled groups of lines, shared by the text
+/// and markdown output.
+fn groups(d: &BcDiff, code: impl Fn(&str) -> String) -> Vec<(String, Vec<String>)> {
+    let mut groups = vec![];
+    let mut push = |title: String, items: Vec<String>| {
+        if !items.is_empty() {
+            groups.push((title, items));
+        }
+    };
+    let changes = |c: &Changes| -> Vec<String> {
+        let added = c.added.iter().map(|p| format!("+ {}", code(p)));
+        added
+            .chain(c.removed.iter().map(|p| format!("- {}", code(p))))
+            .collect()
+    };
+    let bounds = |b: &[String]| match b {
+        [] => "none".to_owned(),
+        _ => b.join(", "),
+    };
+    let causes = |c: &[Cause]| {
+        c.iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join("; ")
+    };
+
+    push(
+        "Renamed sections".to_owned(),
+        d.renamed_sections
+            .iter()
+            .map(|r| format!("{:?} -> {:?}", r.from, r.to))
+            .collect(),
+    );
+    push(
+        "Added sections".to_owned(),
+        d.added_sections.iter().map(|s| format!("{s:?}")).collect(),
+    );
+    push(
+        "Removed sections".to_owned(),
+        d.removed_sections
+            .iter()
+            .map(|s| format!("{s:?}"))
+            .collect(),
+    );
+    for (section, c) in &d.sections {
+        push(format!("Section {section:?}"), changes(c));
+    }
+    push(
+        "Bounds changed".to_owned(),
+        d.bounds
+            .iter()
+            .map(|b| {
+                let package = code(&b.package);
+                format!("{package}: {} -> {}", bounds(&b.old), bounds(&b.new))
+            })
+            .collect(),
+    );
+    push(
+        "Newly disabled".to_owned(),
+        d.disabled
+            .iter()
+            .map(|p| format!("{} ({})", code(&p.package), causes(&p.causes)))
+            .collect(),
+    );
+    push(
+        "Disabled for other reasons".to_owned(),
+        d.causes
+            .iter()
+            .map(|c| {
+                let package = code(&c.package);
+                format!("{package}: {} -> {}", causes(&c.old), causes(&c.new))
+            })
+            .collect(),
+    );
+    push(
+        "Re-enabled".to_owned(),
+        d.enabled.iter().map(|p| code(p)).collect(),
+    );
+    for (key, c) in &d.lists {
+        push(key.clone(), changes(c));
+    }
+    groups
+}
+
+fn print_text(d: &BcDiff) {
+    let groups = groups(d, |s| s.to_owned());
+    if groups.is_empty() {
+        println!("No changes");
+    }
+    for (title, items) in groups {
+        println!("{title}:");
+        for item in items {
+            println!("    {item}");
+        }
+    }
+}
+
+fn print_markdown(d: &BcDiff) {
+    let groups = groups(d, |s| format!("`{s}`"));
+    if groups.is_empty() {
+        println!("No changes");
+    }
+    for (title, items) in groups {
+        println!("### {title}\n");
+        for item in items {
+            // Leading +/- would otherwise be parsed as nested bullets
+            println!("* {item}");
+        }
+        println!();
+    }
+}
+
+#[test]
+fn test_diff() {
+    let lines = |s: &str| -> Vec<String> { s.lines().map(|l| l.to_owned()).collect() };
+    let old = lines(
+        r#"packages:
+    "A <a@a> @a":
+        - foo < 2
+        - bar
+    "B @b":
+        - baz
+    "Library and exe bounds failures":
+        - qux < 0 # tried qux-1, but its *library* does not support: base-5
+        - quux < 0 # tried quux-1, but its *library* requires the disabled package: zap
+        - quux < 0 # tried quux-1, but its *test-suite* requires the disabled package: qux
+        - zap < 0 # tried zap-1
+    "Compilation failures":
+        []
+# end of packages
+skipped-tests:
+    - bar
+"#,
+    );
+    let new = lines(
+        r#"packages:
+    "A <a@a> @a":
+        - foo < 3
+        - new
+    "B <b@b> @b":
+        - baz
+    "Library and exe bounds failures":
+        - bar < 0 # tried bar-1, but its *library* requires the disabled package: zap
+        - quux < 0 # tried quux-1, but its *library* requires the disabled package: zap
+        - quux < 0 # tried quux-1, but its *test-suite* requires the disabled package: baz
+        - zap < 0 # tried zap-1
+    "Compilation failures":
+        - baz < 0
+# end of packages
+skipped-tests:
+    - new
+"#,
+    );
+    let d = diff(&Model::new(&old), &Model::new(&new));
+    let changes = |added: &[&str], removed: &[&str]| Changes {
+        added: added.iter().map(|s| s.to_string()).collect(),
+        removed: removed.iter().map(|s| s.to_string()).collect(),
+    };
+    assert_eq!(
+        d,
+        BcDiff {
+            renamed_sections: vec![Rename {
+                from: "B @b".to_owned(),
+                to: "B <b@b> @b".to_owned(),
+            }],
+            added_sections: vec![],
+            removed_sections: vec![],
+            sections: BTreeMap::from([("A <a@a> @a".to_owned(), changes(&["new"], &["bar"]))]),
+            bounds: vec![BoundChange {
+                package: "foo".to_owned(),
+                old: vec!["< 2".to_owned()],
+                new: vec!["< 3".to_owned()],
+            }],
+            disabled: vec![
+                Disabled {
+                    package: "bar".to_owned(),
+                    causes: vec![Cause::Transitive {
+                        parent: "zap".to_owned()
+                    }],
+                },
+                Disabled {
+                    package: "baz".to_owned(),
+                    causes: vec![Cause::Compile],
+                },
+            ],
+            causes: vec![CausesChange {
+                package: "quux".to_owned(),
+                old: vec![
+                    Cause::Transitive {
+                        parent: "qux".to_owned()
+                    },
+                    Cause::Transitive {
+                        parent: "zap".to_owned()
+                    },
+                ],
+                new: vec![
+                    Cause::Transitive {
+                        parent: "baz".to_owned()
+                    },
+                    Cause::Transitive {
+                        parent: "zap".to_owned()
+                    },
+                ],
+            }],
+            enabled: vec!["qux".to_owned()],
+            lists: BTreeMap::from([("skipped-tests".to_owned(), changes(&["new"], &["bar"]))]),
+        }
+    );
+}
@@ -0,0 +1,112 @@
+use crate::prelude::*;
+
+use lazy_regex::Regex;
+
+use crate::git::{self, Change};
+use crate::sections;
+
+pub fn blame(build_constraints: &Path, package: &str) {
+    // Package names only contain alphanumerics and dashes, so once
+    // validated the name itself does not need escaping.
+    if !regex!(r#"^[A-Za-z0-9]+(-[A-Za-z0-9]+)*$"#).is_match(package) {
+        eprintln!("ERROR: Invalid package name {package:?}");
+        std::process::exit(1);
+    }
+    let pattern = format!("(^|[^A-Za-z0-9-]){package}([^A-Za-z0-9-]|$)");
+    let re = Regex::new(&pattern).unwrap();
+
+    let mut first_seen: Vec<(String, String)> = vec![];
+    for commit in git::log(build_constraints, &pattern) {
+        let changes: Vec<&Change> = commit
+            .changes
+            .iter()
+            .filter(|c| match c {
+                Change::Added(_, l) | Change::Removed(_, l) => re.is_match(l),
+            })
+            .collect();
+        if changes.is_empty() {
+            continue;
+        }
+
+        // Only fetch the revisions when needed
+        let mut after: Option<Vec<String>> = None;
+        let mut before: Option<Vec<String>> = None;
+
+        println!(
+            "{date} {hash} {author}: {subject}",
+            date = commit.date,
+            hash = &commit.hash[..commit.hash.len().min(10)],
+            author = commit.author,
+            subject = commit.subject,
+        );
+        for change in changes {
+            let (sign, i, line, lines) = match change {
+                Change::Added(i, line) => (
+                    '+',
+                    i,
+                    line,
+                    &*after.get_or_insert_with(|| git::show(&commit.hash, build_constraints)),
+                ),
+                Change::Removed(i, line) => (
+                    '-',
+                    i,
+                    line,
+                    &*before.get_or_insert_with(|| {
+                        git::show(&format!("{}^", commit.hash), build_constraints)
+                    }),
+                ),
+            };
+            let location = location(lines, *i);
+            println!("    {sign} {location}: {}", line.trim());
+            if sign == '+' && !first_seen.iter().any(|(l, _)| *l == location) {
+                first_seen.push((location, commit.date.clone()));
+            }
+        }
+    }
+
+    if !first_seen.is_empty() {
+        println!();
+        for (location, date) in first_seen {
+            println!("First added to {location} on {date}");
+        }
+    }
+}
+
+/// The section under `packages:`, or otherwise the top level key, that
+/// contains line `i`.
+fn location(lines: &[String], i: usize) -> String {
+    if let Some(section) = sections::sections(lines)
+        .into_iter()
+        .find(|s| s.header <= i && i < s.end())
+    {
+        return format!("{:?}", section.name);
+    }
+    lines[..=i.min(lines.len().saturating_sub(1))]
+        .iter()
+        .rev()
+        .find_map(|l| {
+            regex!(r#"^([^ #-][^:]*):"#)
+                .captures(l)
+                .map(|c| c[1].to_owned())
+        })
+        .unwrap_or_else(|| "?".to_owned())
+}
+
+#[test]
+fn test_location() {
+    let lines: Vec<String> = [
+        "ghc-version: 9.0.2",
+        "packages:",
+        "    \"A\":",
+        "        - foo",
+        "# end of packages",
+        "skipped-tests:",
+        "    - foo",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    assert_eq!(location(&lines, 0), "ghc-version");
+    assert_eq!(location(&lines, 3), "\"A\"");
+    assert_eq!(location(&lines, 6), "skipped-tests");
+}
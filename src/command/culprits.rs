@@ -0,0 +1,191 @@
+use crate::prelude::*;
+
+use crate::bounds_failure::{BoundsFailure, Cause};
+use crate::command::disabled;
+use crate::handle::{handle, Location};
+use crate::types::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Culprit {
+    Version(VersionedPackage),
+    Disabled(Package),
+}
+
+impl Culprit {
+    pub(crate) fn new(cause: &Cause) -> Culprit {
+        match cause {
+            Cause::DisabledPackage(parent) => Culprit::Disabled(parent.clone()),
+            cause => Culprit::Version(cause.snapshot_version().unwrap().clone()),
+        }
+    }
+
+    /// For use in file names.
+    pub(crate) fn slug(&self) -> String {
+        match self {
+            Culprit::Version(v) => v.to_string(),
+            Culprit::Disabled(p) => format!("{p}-disabled"),
+        }
+    }
+}
+
+impl fmt::Display for Culprit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Culprit::Version(v) => v.fmt(f),
+            Culprit::Disabled(p) => write!(f, "{p} (disabled)"),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Blocked {
+    pub(crate) libs: BTreeSet<Package>,
+    pub(crate) tests: BTreeSet<Package>,
+    pub(crate) benches: BTreeSet<Package>,
+}
+
+pub fn culprits(build_constraints: &Path) {
+    let culprits = blocked(build_constraints);
+
+    let mut disabled = disabled::disabled_map(build_constraints);
+    if let Err(cycle) = disabled::count_dependents(&mut disabled) {
+        eprintln!("ERROR: {cycle}");
+        std::process::exit(1);
+    }
+
+    for (transitive, culprit, blocked) in rank(culprits, &disabled) {
+        println!(
+            "{culprit}: {direct} direct, {transitive} transitive, {tests} test suites, {benches} benchmarks",
+            direct = blocked.libs.len(),
+            tests = blocked.tests.len(),
+            benches = blocked.benches.len(),
+        );
+    }
+}
+
+/// The culprits with the number of libraries they keep out of the
+/// snapshot transitively, most first. Ties are broken by the number of
+/// blocked test suites and benchmarks.
+fn rank(
+    culprits: BTreeMap<Culprit, Blocked>,
+    disabled: &disabled::M,
+) -> Vec<(usize, Culprit, Blocked)> {
+    let mut v: Vec<(usize, Culprit, Blocked)> = culprits
+        .into_iter()
+        .map(|(culprit, blocked)| {
+            let transitive = blocked
+                .libs
+                .iter()
+                .map(|p| 1 + disabled.get(p).and_then(|(_, count)| *count).unwrap_or(0))
+                .sum();
+            (transitive, culprit, blocked)
+        })
+        .collect();
+    v.sort_by(|(t1, c1, b1), (t2, c2, b2)| {
+        t2.cmp(t1)
+            .then_with(|| {
+                (b2.tests.len() + b2.benches.len()).cmp(&(b1.tests.len() + b1.benches.len()))
+            })
+            .then_with(|| c1.cmp(c2))
+    });
+    v
+}
+
+/// The packages blocked by each culprit, per component kind.
+pub(crate) fn blocked(build_constraints: &Path) -> BTreeMap<Culprit, Blocked> {
+    let mut culprits: BTreeMap<Culprit, Blocked> = BTreeMap::new();
+    handle(build_constraints, false, |loc, lines| {
+        add_blocked(&mut culprits, loc, &lines);
+        vec![]
+    });
+    culprits
+}
+
+fn add_blocked(culprits: &mut BTreeMap<Culprit, Blocked>, loc: Location, lines: &[String]) {
+    for line in lines {
+        if let Some((package, culprit)) = parse_culprit(line) {
+            let blocked = culprits.entry(culprit).or_default();
+            match loc {
+                Location::Lib => blocked.libs.insert(package),
+                Location::Test => blocked.tests.insert(package),
+                Location::Bench => blocked.benches.insert(package),
+            };
+        }
+    }
+}
+
+pub(crate) fn parse_culprit(s: &str) -> Option<(Package, Culprit)> {
+    let BoundsFailure { package, cause, .. } = BoundsFailure::parse(s)?;
+    Some((package, Culprit::new(&cause)))
+}
+
+#[test]
+fn test_parse_culprit() {
+    fn versioned(package: &str, version: &str) -> Culprit {
+        Culprit::Version(VersionedPackage {
+            package: package.into(),
+            version: version.try_into().unwrap(),
+        })
+    }
+
+    assert_eq!(
+        parse_culprit(
+            "        - zm < 0 # tried zm-0.3.2, but its *library* does not support: flat-0.4.4"
+        ),
+        Some(("zm".into(), versioned("flat", "0.4.4")))
+    );
+    assert_eq!(
+        parse_culprit("    - ENIG # tried ENIG-0.0.1.0, but its *test-suite* requires the disabled package: test-framework-th"),
+        Some(("ENIG".into(), Culprit::Disabled("test-framework-th".into())))
+    );
+    assert_eq!(
+        parse_culprit("        - b9 < 0 # tried b9-3.2.0, but its *library* requires aeson ==1.4.* and the snapshot contains aeson-2.0.3.0"),
+        Some(("b9".into(), versioned("aeson", "2.0.3.0")))
+    );
+    assert_eq!(parse_culprit("        - mmorph < 1.2"), None);
+}
+
+#[test]
+fn test_rank() {
+    let lines = |s: &[&str]| -> Vec<String> { s.iter().map(|l| l.to_string()).collect() };
+    // aeson-2 keeps out a, which keeps out b, which keeps out c
+    let lib = lines(&[
+        "        - a < 0 # tried a-1, but its *library* requires aeson ==1.* and the snapshot contains aeson-2",
+        "        - b < 0 # tried b-1, but its *library* requires the disabled package: a",
+        "        - c < 0 # tried c-1, but its *library* requires the disabled package: b",
+        "        - d < 0 # tried d-1, but its *library* requires text <2 and the snapshot contains text-2",
+        "        - e < 0 # tried e-1, but its *library* requires text <2 and the snapshot contains text-2",
+    ]);
+    let test = lines(&[
+        "    - f # tried f-1, but its *test-suite* requires the disabled package: a",
+        "    - g # tried g-1, but its *test-suite* requires hspec <3 and the snapshot contains hspec-3",
+    ]);
+    let mut culprits = BTreeMap::new();
+    add_blocked(&mut culprits, Location::Lib, &lib);
+    add_blocked(&mut culprits, Location::Test, &test);
+    let mut disabled = disabled::disabled_map_from(&lib);
+    disabled::count_dependents(&mut disabled).unwrap();
+
+    let ranked: Vec<(String, usize, usize, usize)> = rank(culprits, &disabled)
+        .into_iter()
+        .map(|(transitive, culprit, blocked)| {
+            (
+                culprit.to_string(),
+                blocked.libs.len(),
+                transitive,
+                blocked.tests.len(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        ranked,
+        vec![
+            ("aeson-2".to_owned(), 1, 3, 0),
+            // Ties go to the culprit blocking more test suites
+            ("a (disabled)".to_owned(), 1, 2, 1),
+            ("text-2".to_owned(), 2, 2, 0),
+            ("b (disabled)".to_owned(), 1, 1, 0),
+            ("hspec-3".to_owned(), 0, 0, 1),
+        ]
+    );
+}
@@ -1,32 +1,80 @@
 use crate::prelude::*;
 
+use crate::bounds_failure::{BoundsFailure, Cause};
 use crate::handle::{handle, Location};
-use crate::regex::*;
 use crate::types::*;
 
+use itertools::Itertools;
+use serde::Serialize;
+
 pub(crate) type M = BTreeMap<Package, (Vec<VersionedPackage>, Option<usize>)>;
 
-pub fn disabled(build_constraints: &Path) {
-    let mut disabled_transitively: Vec<DisabledTransitively> = vec![];
+#[derive(Debug, Clone, Copy, strum::EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum GraphFormat {
+    Dot,
+    Json,
+}
+
+pub fn disabled(build_constraints: &Path, tree: Option<&str>, graph: Option<GraphFormat>) {
+    let mut disabled = disabled_map(build_constraints);
+
+    if let Some(package) = tree {
+        print_tree(&disabled, &Package::from(package));
+        return;
+    }
+
+    if let Some(format) = graph {
+        match format {
+            GraphFormat::Dot => print_dot(&disabled),
+            GraphFormat::Json => print_json(&disabled),
+        }
+        return;
+    }
+
+    if let Err(cycle) = count_dependents(&mut disabled) {
+        eprintln!("ERROR: {cycle}");
+        std::process::exit(1);
+    }
+
+    let mut v: Vec<_> = disabled
+        .into_iter()
+        .map(|(package, (_, count))| (count, package))
+        .collect();
+    v.sort();
+    for (count, package) in v {
+        let count = count.unwrap();
+        if count != 0 {
+            println!("{package} is disabled with {count} dependents");
+        }
+    }
+}
+
+/// Builds the parent -> children map from all "requires the disabled
+/// package" entries in the lib/exe bounds block. Counts are left
+/// empty, see `count_dependents`.
+pub(crate) fn disabled_map(build_constraints: &Path) -> M {
+    let mut lib: Vec<String> = vec![];
     handle(build_constraints, false, |loc, lines| {
         match loc {
-            Location::Lib => disabled_transitively.extend(
-                lines
-                    .into_iter()
-                    .filter_map(|line| parse_disabled_transitviely(&line))
-                    .collect::<Vec<_>>(),
-            ),
+            Location::Lib => lib.extend(lines),
             Location::Test | Location::Bench => (),
         }
         vec![]
     });
+    disabled_map_from(&lib)
+}
+
+/// Like `disabled_map`, given the lines of the lib/exe bounds block.
+pub(crate) fn disabled_map_from(lib: &[String]) -> M {
+    let disabled_transitively: Vec<DisabledTransitively> = lib
+        .iter()
+        .filter_map(|line| parse_disabled_transitviely(line))
+        .collect();
 
-    let mut packages: BTreeSet<Package> = BTreeSet::new();
     let mut disabled: M = BTreeMap::new();
 
     for DisabledTransitively { child, parent } in disabled_transitively {
-        packages.insert(child.package.clone());
-        packages.insert(parent.clone());
         disabled
             .entry(child.package.clone())
             .or_insert_with(|| (vec![], None));
@@ -34,30 +82,100 @@ pub fn disabled(build_constraints: &Path) {
         t.0.push(child);
     }
 
-    let mut packages_len = packages.len();
-    while packages_len > 0 {
-        let mut new_packages: BTreeSet<Package> = BTreeSet::new();
-        for package in packages {
-            let (_, count) = disabled.get(&package).unwrap();
-            if count.is_none() && !process(&package, &mut disabled) {
-                new_packages.insert(package.clone());
-            }
+    disabled
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Cycle(pub(crate) Vec<Package>);
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cycle in disabled packages: {}",
+            self.0.iter().map(|p| p.to_string()).join(" -> ")
+        )
+    }
+}
+
+/// Fills in the transitive number of dependents for every package in
+/// the map, failing if the disabled packages depend on each other in a
+/// cycle.
+pub(crate) fn count_dependents(m: &mut M) -> Result<(), Cycle> {
+    let packages: Vec<Package> = m.keys().cloned().collect();
+    for package in packages {
+        process(&package, m, &mut vec![])?;
+    }
+    Ok(())
+}
+
+fn print_tree(m: &M, root: &Package) {
+    match m.get(root) {
+        None => println!("{root} is not disabled by any bounds failures"),
+        Some((children, _)) if children.is_empty() => println!("{root} has no disabled dependents"),
+        Some(_) => {
+            println!("{root}");
+            print_subtree(m, root, &mut vec![root.clone()], 1);
         }
-        packages = new_packages;
-        packages_len = packages.len();
     }
+}
 
-    let mut v: Vec<_> = disabled
-        .into_iter()
-        .map(|(package, (_, count))| (count, package))
-        .collect();
-    v.sort();
-    for (count, package) in v {
-        let count = count.unwrap();
-        if count != 0 {
-            println!("{package} is disabled with {count} dependents");
+fn print_subtree(m: &M, package: &Package, path: &mut Vec<Package>, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let (children, _) = m.get(package).unwrap();
+    for child in children.iter().unique() {
+        if path.contains(&child.package) {
+            println!("{indent}{child} (cycle)");
+            continue;
         }
+        println!("{indent}{child}");
+        path.push(child.package.clone());
+        print_subtree(m, &child.package, path, depth + 1);
+        path.pop();
+    }
+}
+
+fn edges(m: &M) -> impl Iterator<Item = (&Package, &VersionedPackage)> {
+    m.iter()
+        .flat_map(|(parent, (children, _))| children.iter().unique().map(move |c| (parent, c)))
+}
+
+fn print_dot(m: &M) {
+    println!("digraph disabled {{");
+    for (parent, child) in edges(m) {
+        println!(
+            "    \"{parent}\" -> \"{package}\";",
+            package = child.package
+        );
     }
+    println!("}}");
+}
+
+#[derive(Serialize)]
+struct JsonGraph {
+    packages: Vec<String>,
+    edges: Vec<JsonEdge>,
+}
+
+#[derive(Serialize)]
+struct JsonEdge {
+    parent: String,
+    child: String,
+    version: String,
+}
+
+fn print_json(m: &M) {
+    let graph = JsonGraph {
+        packages: m.keys().map(|p| p.to_string()).collect(),
+        edges: edges(m)
+            .map(|(parent, child)| JsonEdge {
+                parent: parent.to_string(),
+                child: child.package.to_string(),
+                version: child.version.to_string(),
+            })
+            .collect(),
+    };
+    println!("{}", serde_json::to_string_pretty(&graph).unwrap());
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -67,18 +185,22 @@ pub(crate) struct DisabledTransitively {
 }
 
 pub(crate) fn parse_disabled_transitviely(s: &str) -> Option<DisabledTransitively> {
-    let r = regex!(
-        r#"- *([^ ]+) < *0 *# tried [^ ]+-([\d.]+), but its \*[^*]+\* requires the disabled package: ([^ ]+)"#
-    );
-    Captures::new(r, s).ok().map(|cap| {
-        let package = cap.get(1).unwrap();
-        let version = cap.try_get(2).unwrap();
-        let parent = cap.try_get(3).unwrap();
-        DisabledTransitively {
-            child: VersionedPackage { package, version },
+    match BoundsFailure::parse(s)? {
+        BoundsFailure {
+            package,
+            disabled: true,
+            tried,
+            cause: Cause::DisabledPackage(parent),
+            ..
+        } => Some(DisabledTransitively {
+            child: VersionedPackage {
+                package,
+                version: tried.version,
+            },
             parent,
-        }
-    })
+        }),
+        _ => None,
+    }
 }
 
 #[test]
@@ -93,24 +215,58 @@ fn test_parse_disabled_transitviely() {
             },
             parent: Package("mstate".to_owned()),
         })
-    )
+    );
+    let tagged = format!("{s} [nightly-2023-01-01 2023-01-02]");
+    assert_eq!(
+        parse_disabled_transitviely(&tagged),
+        parse_disabled_transitviely(s)
+    );
 }
 
-fn process(package: &Package, m: &mut M) -> bool {
-    let (children, count) = m.get(package).unwrap();
-    assert!(count.is_none(), "{:?}", package);
+fn process(package: &Package, m: &mut M, path: &mut Vec<Package>) -> Result<usize, Cycle> {
+    let (children, count) = m.get(package).unwrap_or_else(|| panic!("{}", package));
+    if let Some(count) = count {
+        return Ok(*count);
+    }
+    if let Some(i) = path.iter().position(|p| p == package) {
+        let mut cycle = path[i..].to_vec();
+        cycle.push(package.clone());
+        return Err(Cycle(cycle));
+    }
+    let children: Vec<Package> = children.iter().map(|c| c.package.clone()).collect();
+    path.push(package.clone());
     let mut count = 0;
     for child in children {
-        let (_, child_count) = m
-            .get(&child.package)
-            .unwrap_or_else(|| panic!("{}", child.package));
-        match child_count {
-            None => return false,
-            Some(i) => count += 1 + i,
-        }
+        count += 1 + process(&child, m, path)?;
     }
+    path.pop();
     m.entry(package.clone())
-        .and_modify(|tup| tup.1 = Some(count))
-        .or_insert_with(|| panic!("{}", package));
-    true
+        .and_modify(|tup| tup.1 = Some(count));
+    Ok(count)
+}
+
+#[test]
+fn test_count_dependents() {
+    let mut m = disabled_map(&PathBuf::from("test/build-constraints.yaml"));
+    count_dependents(&mut m).unwrap();
+    assert_eq!(m.get(&Package::from("mstate")).unwrap().1, Some(1));
+    assert!(m.values().all(|(_, count)| count.is_some()));
+}
+
+#[test]
+fn test_count_dependents_cycle() {
+    fn child(package: &str) -> VersionedPackage {
+        VersionedPackage {
+            package: package.into(),
+            version: "1.0".try_into().unwrap(),
+        }
+    }
+    let mut m: M = BTreeMap::new();
+    m.insert("a".into(), (vec![child("b")], None));
+    m.insert("b".into(), (vec![child("c")], None));
+    m.insert("c".into(), (vec![child("a")], None));
+    assert_eq!(
+        count_dependents(&mut m),
+        Err(Cycle(vec!["a".into(), "b".into(), "c".into(), "a".into()]))
+    );
 }
@@ -0,0 +1,283 @@
+use crate::prelude::*;
+
+use crate::build_constraints::SPECIAL_SECTIONS;
+use crate::handle::{bounds_blocks, Location};
+use crate::sections;
+
+const HEADER_INDENT: &str = "    ";
+const ENTRY_INDENT: &str = "        ";
+
+/// Special section that is sorted like a maintainer section since all
+/// its entries are generated.
+const LIB_BOUNDS: &str = "Library and exe bounds failures";
+
+pub fn fmt(build_constraints: &Path, check: bool) {
+    let lines = sections::read(build_constraints);
+    let formatted = format(&lines);
+
+    if check {
+        if let Some(i) = lines
+            .iter()
+            .zip(formatted.iter())
+            .position(|(a, b)| a != b)
+            .or_else(|| (lines.len() != formatted.len()).then(|| lines.len().min(formatted.len())))
+        {
+            println!(
+                "{file} is not formatted, first difference on line {line}",
+                file = build_constraints.display(),
+                line = i + 1
+            );
+            std::process::exit(1);
+        }
+    } else if formatted != lines {
+        fs::write_lines(build_constraints, formatted);
+    }
+}
+
+#[derive(Debug)]
+enum Item {
+    Blank,
+    /// Comment lines not directly followed by an entry, kept verbatim.
+    Comments(Vec<String>),
+    /// An entry together with the comment lines directly above it.
+    Entry(Vec<String>),
+    /// Anything else, kept verbatim.
+    Other(String),
+}
+
+#[derive(Debug)]
+struct Section {
+    leading: Vec<String>,
+    header: String,
+    name: String,
+    items: Vec<Item>,
+}
+
+fn format(lines: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = vec![];
+    let Some(start) = lines.iter().position(|l| l == "packages:") else {
+        return lines.to_vec();
+    };
+    let end = lines
+        .iter()
+        .skip(start + 1)
+        .position(|l| l == "# end of packages" || regex!(r#"^[^ #]"#).is_match(l))
+        .map(|i| i + start + 1)
+        .unwrap_or(lines.len());
+
+    out.extend_from_slice(&lines[..=start]);
+    let (before, sections) = parse(&lines[start + 1..end]);
+    for line in before {
+        out.push(line);
+    }
+    for section in sections {
+        out.push(String::new());
+        out.extend(render(section));
+    }
+    out.push(String::new());
+    out.extend_from_slice(&lines[end..]);
+
+    for (loc, range) in bounds_blocks(&out) {
+        if loc != Location::Lib {
+            sections::sort_entries(&mut out[range], sections::sort_key);
+        }
+    }
+    out
+}
+
+/// Splits the lines under `packages:` into sections, returning any
+/// non-blank lines before the first section separately.
+fn parse(lines: &[String]) -> (Vec<String>, Vec<Section>) {
+    let mut before: Vec<String> = vec![];
+    let mut sections: Vec<Section> = vec![];
+    let mut pending: Vec<String> = vec![];
+
+    fn push(sections: &mut [Section], before: &mut Vec<String>, item: Item) {
+        match sections.last_mut() {
+            Some(section) => section.items.push(item),
+            None => match item {
+                Item::Blank => {}
+                Item::Comments(c) => before.extend(c),
+                Item::Entry(e) => before.extend(e),
+                Item::Other(o) => before.push(o),
+            },
+        }
+    }
+
+    for line in lines {
+        if line.trim().is_empty() {
+            if !pending.is_empty() {
+                let comments = std::mem::take(&mut pending);
+                push(&mut sections, &mut before, Item::Comments(comments));
+            }
+            push(&mut sections, &mut before, Item::Blank);
+        } else if line.trim_start().starts_with('#') {
+            pending.push(line.clone());
+        } else if let Some(name) = sections::parse_header(line) {
+            sections.push(Section {
+                leading: std::mem::take(&mut pending),
+                header: line.trim_end().to_owned(),
+                name,
+                items: vec![],
+            });
+        } else if sections::parse_entry(line).is_some() {
+            let mut entry = std::mem::take(&mut pending);
+            entry.push(line.clone());
+            push(&mut sections, &mut before, Item::Entry(entry));
+        } else {
+            if !pending.is_empty() {
+                let comments = std::mem::take(&mut pending);
+                push(&mut sections, &mut before, Item::Comments(comments));
+            }
+            push(&mut sections, &mut before, Item::Other(line.clone()));
+        }
+    }
+    if !pending.is_empty() {
+        push(&mut sections, &mut before, Item::Comments(pending));
+    }
+    (before, sections)
+}
+
+fn render(section: Section) -> Vec<String> {
+    let Section {
+        leading,
+        header,
+        name,
+        items,
+    } = section;
+    let sorted = name == LIB_BOUNDS || !SPECIAL_SECTIONS.contains(&&*name);
+
+    let mut out: Vec<String> = leading
+        .iter()
+        .map(|l| format!("{HEADER_INDENT}{}", l.trim()))
+        .collect();
+    out.push(header);
+
+    let mut items: Vec<Item> = if sorted {
+        items
+            .into_iter()
+            .filter(|i| !matches!(i, Item::Blank))
+            .collect()
+    } else {
+        items
+    };
+
+    if sorted {
+        // Sort each run of entries that is not interrupted by a
+        // free-standing comment.
+        let mut start = 0;
+        while start < items.len() {
+            let len = items[start..]
+                .iter()
+                .position(|i| !matches!(i, Item::Entry(_)))
+                .unwrap_or(items.len() - start);
+            items[start..start + len].sort_by_key(|i| match i {
+                Item::Entry(lines) => sections::sort_key(lines.last().unwrap()),
+                _ => unreachable!(),
+            });
+            start += len + 1;
+        }
+    }
+
+    for item in items {
+        match item {
+            Item::Blank => {
+                if out.last().is_some_and(|l| !l.is_empty()) {
+                    out.push(String::new());
+                }
+            }
+            Item::Comments(c) => out.extend(c),
+            Item::Entry(e) => out.extend(e.iter().map(|l| format!("{ENTRY_INDENT}{}", l.trim()))),
+            Item::Other(o) => out.push(o),
+        }
+    }
+    while out.last().is_some_and(|l| l.is_empty()) {
+        out.pop();
+    }
+    out
+}
+
+#[test]
+fn test_format() {
+    let lines: Vec<String> = [
+        "ghc-version: \"9.0.2\"",
+        "packages:",
+        "    \"A <a@a> @a\":",
+        "        - zoo",
+        "",
+        "      # about bar",
+        "      - Bar < 1",
+        "        - apple",
+        "",
+        "",
+        "     # comment about B",
+        "    \"B\": []",
+        "    \"Stackage upper bounds\":",
+        "",
+        "      # group",
+        "      - z < 1",
+        "",
+        "",
+        "      - a < 1",
+        "",
+        "# end of packages",
+        "skipped-tests:",
+        "    # Test bounds issues",
+        "    - b # tried b-1",
+        "    - A # tried A-1",
+        "    # End of Test bounds issues",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let expected: Vec<String> = [
+        "ghc-version: \"9.0.2\"",
+        "packages:",
+        "",
+        "    \"A <a@a> @a\":",
+        "        - apple",
+        "        # about bar",
+        "        - Bar < 1",
+        "        - zoo",
+        "",
+        "    # comment about B",
+        "    \"B\": []",
+        "",
+        "    \"Stackage upper bounds\":",
+        "",
+        "        # group",
+        "        - z < 1",
+        "",
+        "        - a < 1",
+        "",
+        "# end of packages",
+        "skipped-tests:",
+        "    # Test bounds issues",
+        "    - A # tried A-1",
+        "    - b # tried b-1",
+        "    # End of Test bounds issues",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    assert_eq!(format(&lines), expected);
+    assert_eq!(format(&expected), expected);
+}
+
+#[test]
+fn test_format_idempotent() {
+    let lines = sections::read(&PathBuf::from("test/build-constraints.yaml"));
+    let formatted = format(&lines);
+    assert_eq!(format(&formatted), formatted);
+
+    let packages = |lines: &[String]| -> BTreeMap<String, BTreeSet<String>> {
+        sections::sections(lines)
+            .into_iter()
+            .map(|s| {
+                let entries = s.entries.iter().map(|e| lines[e.line].trim().to_owned());
+                (s.name, entries.collect())
+            })
+            .collect()
+    };
+    assert_eq!(packages(&lines), packages(&formatted));
+}
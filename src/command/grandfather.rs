@@ -1,41 +1,111 @@
 use crate::prelude::*;
 
-use crate::command::disabled::{self, DisabledTransitively, M};
-use crate::handle::{self, Location};
-
-pub fn grandfather(build_constraints: &Path) {
-    let mut disabled_transitively: Vec<DisabledTransitively> = vec![];
-    handle::handle(build_constraints, false, |loc, lines| {
-        match loc {
-            Location::Lib => disabled_transitively.extend(
-                lines
-                    .into_iter()
-                    .filter_map(|line| disabled::parse_disabled_transitviely(&line))
-                    .collect::<Vec<_>>(),
-            ),
-            Location::Test | Location::Bench => (),
-        }
-        vec![]
-    });
+use crate::cabal;
+use crate::command::culprits::{self, Culprit};
+use crate::command::disabled;
+use crate::handle::handle;
+use crate::latest_version;
+use crate::sections;
+use crate::snapshot::SnapshotYaml;
+use crate::types::*;
+use crate::yaml;
 
-    let mut disabled: M = BTreeMap::new();
+const GRANDFATHERED: &str = "Grandfathered dependencies";
 
-    for DisabledTransitively { child, parent } in disabled_transitively {
-        disabled
-            .entry(child.package.clone())
-            .or_insert_with(|| (vec![], None));
-        let t = disabled.entry(parent).or_insert_with(|| (vec![], None));
-        t.0.push(child);
+pub fn grandfather(build_constraints: &Path, write: bool, prune: bool, snapshot: Option<&Path>) {
+    if prune {
+        let snapshot = snapshot.expect("--prune requires --snapshot");
+        return grandfather_prune(build_constraints, write, snapshot);
     }
 
+    let disabled = disabled::disabled_map(build_constraints);
+
     let bc = crate::build_constraints::parse(build_constraints).by_package();
 
+    let mut lines = sections::read(build_constraints);
+
     for (parent, (children, _)) in disabled.into_iter() {
         if children.is_empty() {
             continue;
         }
         if bc.package(&parent).is_none() {
             println!("        - {parent}");
+            if write {
+                sections::insert_sorted(&mut lines, GRANDFATHERED, &parent, &parent.to_string());
+            }
+        }
+    }
+
+    if write {
+        fs::write_lines(build_constraints, lines);
+    }
+}
+
+/// Grandfathered packages that nothing in the snapshot depends on and
+/// that no bounds failure mentions.
+fn grandfather_prune(build_constraints: &Path, write: bool, snapshot: &Path) {
+    let mut referenced: BTreeSet<Package> = BTreeSet::new();
+    handle(build_constraints, false, |_loc, lines| {
+        for line in lines {
+            if let Some((package, culprit)) = culprits::parse_culprit(&line) {
+                referenced.insert(package);
+                referenced.insert(match culprit {
+                    Culprit::Version(v) => v.package,
+                    Culprit::Disabled(p) => p,
+                });
+            }
         }
+        vec![]
+    });
+
+    let snapshot: SnapshotYaml = yaml::yaml_from_file(snapshot)
+        .unwrap_or_else(|e| panic!("Error reading snapshot {snapshot:?}: {e}"));
+    let packages: Vec<VersionedPackage> =
+        snapshot.packages.into_iter().map(|p| p.hackage.0).collect();
+    let cabal_files = latest_version::cabal_files(packages.iter());
+    // Without a cabal file the dependencies are unknown, so anything
+    // grandfathered could still be needed
+    let unresolved: Vec<&VersionedPackage> = packages
+        .iter()
+        .filter(|p| !cabal_files.contains_key(p))
+        .collect();
+    for p in &unresolved {
+        println!("WARN: No cabal file in pantry for {p}");
+    }
+    if write && !unresolved.is_empty() {
+        eprintln!(
+            "ERROR: Not pruning since {} snapshot packages are missing from pantry, run `stack update` first",
+            unresolved.len()
+        );
+        std::process::exit(1);
+    }
+
+    let mut depended: BTreeSet<Package> = BTreeSet::new();
+    for (VersionedPackage { package, .. }, cabal) in cabal_files {
+        depended.extend(
+            cabal::build_depends(&cabal)
+                .into_iter()
+                .filter(|dep| *dep != package),
+        );
+    }
+
+    let mut lines = sections::read(build_constraints);
+    let unused: BTreeSet<Package> = sections::sections(&lines)
+        .into_iter()
+        .find(|s| s.name == GRANDFATHERED)
+        .map(|s| s.entries)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|e| e.package)
+        .filter(|p| !depended.contains(p) && !referenced.contains(p))
+        .collect();
+
+    for package in &unused {
+        println!("        - {package}");
+    }
+
+    if write {
+        sections::remove(&mut lines, GRANDFATHERED, &unused);
+        fs::write_lines(build_constraints, lines);
     }
 }
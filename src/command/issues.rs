@@ -0,0 +1,190 @@
+use crate::prelude::*;
+
+use crate::bounds_failure::{BoundsFailure, Cause};
+use crate::command::culprits::Culprit;
+use crate::command::maintainer::maintainer_sections;
+use crate::handle::bounds_blocks;
+use crate::sections;
+use crate::types::{Package, VersionedPackage};
+
+/// Writes a stackage issue body for each culprit of the generated
+/// bounds to `out_dir`.
+pub fn issues(build_constraints: &Path, out_dir: &Path) {
+    let lines = sections::read(build_constraints);
+    let failures: Vec<BoundsFailure> = bounds_blocks(&lines)
+        .into_iter()
+        .flat_map(|(_, range)| lines[range].iter().filter_map(|l| BoundsFailure::parse(l)))
+        .collect();
+    write_issues(&lines, &failures, out_dir);
+}
+
+/// Writes `<culprit>.md` for each culprit of `failures`, printing the
+/// file names and issue titles. `lines` are the build-constraints used
+/// to look up maintainers.
+pub(crate) fn write_issues(lines: &[String], failures: &[BoundsFailure], out_dir: &Path) {
+    fs::create_dir_all(out_dir).unwrap();
+    for (culprit, body) in bodies(lines, failures) {
+        let path = out_dir.join(format!("{}.md", culprit.slug()));
+        println!("{}: {}", path.display(), title(&culprit));
+        fs::write_lines(&path, body);
+    }
+}
+
+fn title(culprit: &Culprit) -> String {
+    match culprit {
+        Culprit::Version(v) => format!("{v} is out of bounds"),
+        Culprit::Disabled(p) => format!("{p} is disabled"),
+    }
+}
+
+/// The components of an affected package with their bounds on the
+/// culprit, if known.
+type Affected = Vec<(String, Option<String>)>;
+
+fn bodies(lines: &[String], failures: &[BoundsFailure]) -> BTreeMap<Culprit, Vec<String>> {
+    let mut grouped: BTreeMap<Culprit, BTreeMap<VersionedPackage, Affected>> = BTreeMap::new();
+    for failure in failures {
+        let affected = grouped
+            .entry(Culprit::new(&failure.cause))
+            .or_default()
+            .entry(failure.tried.clone())
+            .or_default();
+        let bound = match &failure.cause {
+            Cause::OutOfBounds { bound, .. } => Some(bound.clone()),
+            _ => None,
+        };
+        for component in failure.component.split(", ") {
+            if !affected.iter().any(|(c, _)| c == component) {
+                affected.push((component.to_owned(), bound.clone()));
+            }
+        }
+    }
+
+    let maintainers = maintainers(lines);
+    grouped
+        .into_iter()
+        .map(|(culprit, affected)| {
+            let mut body = vec![
+                match &culprit {
+                    Culprit::Version(v) => format!("{v} is out of bounds for:"),
+                    Culprit::Disabled(p) => {
+                        format!("{p} is disabled, which keeps out the following packages:")
+                    }
+                },
+                String::new(),
+            ];
+            for (tried, components) in affected {
+                let mut bounds: Vec<&Option<String>> = components.iter().map(|(_, b)| b).collect();
+                bounds.sort();
+                bounds.dedup();
+                // A single bound is shown once, otherwise per component
+                let (bound, components): (String, Vec<String>) = match bounds[..] {
+                    [bound] => (
+                        bound
+                            .as_ref()
+                            .map(|b| format!(" ({b})"))
+                            .unwrap_or_default(),
+                        components.into_iter().map(|(c, _)| c).collect(),
+                    ),
+                    _ => (
+                        String::new(),
+                        components
+                            .into_iter()
+                            .map(|(c, b)| match b {
+                                Some(b) => format!("{c} ({b})"),
+                                None => c,
+                            })
+                            .collect(),
+                    ),
+                };
+                let maintainer = maintainers
+                    .get(&tried.package)
+                    .map_or("No maintainer", |m| m);
+                body.push(format!(
+                    "- [ ] {tried}{bound}. {maintainer}. Used by: {}",
+                    components.join(", ")
+                ));
+            }
+            (culprit, body)
+        })
+        .collect()
+}
+
+/// The `@handles` of everyone maintaining each package, or the section
+/// headers if there are none.
+fn maintainers(lines: &[String]) -> BTreeMap<Package, String> {
+    let mut sections: BTreeMap<Package, Vec<_>> = BTreeMap::new();
+    for (m, entries) in maintainer_sections(lines) {
+        for (package, _) in entries {
+            sections.entry(package).or_default().push(m.clone());
+        }
+    }
+    sections
+        .into_iter()
+        .map(|(package, ms)| {
+            let handles: Vec<&str> = ms
+                .iter()
+                .flat_map(|m| m.handles.iter().map(|h| h.as_str()))
+                .collect();
+            let s = if handles.is_empty() {
+                ms.iter()
+                    .map(|m| m.header.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            } else {
+                handles.join(" ")
+            };
+            (package, s)
+        })
+        .collect()
+}
+
+#[test]
+fn test_bodies() {
+    let lines: Vec<String> = [
+        "packages:",
+        "    \"A @a\":",
+        "        - foo",
+        "        - bar",
+        "    \"B <b@b.com>\":",
+        "        - baz",
+        "# end of packages",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let failures: Vec<BoundsFailure> = [
+        "- foo < 0 # tried foo-1, but its *library* requires aeson ==1.* and the snapshot contains aeson-2.0",
+        "- foo # tried foo-1, but its *test-suite* requires aeson ==1.* and the snapshot contains aeson-2.0",
+        "- baz < 0 # tried baz-2, but its *library* does not support: aeson-2.0",
+        "- qux < 0 # tried qux-3, but its *library* requires aeson >=1 && <1.5 and the snapshot contains aeson-2.0",
+        "- qux # tried qux-3, but its *benchmarks* requires aeson <1.5 and the snapshot contains aeson-2.0",
+        "- bar < 0 # tried bar-1, but its *library* requires the disabled package: foo",
+    ]
+    .iter()
+    .map(|s| BoundsFailure::parse(s).unwrap())
+    .collect();
+    let bodies = bodies(&lines, &failures);
+    let aeson = Culprit::Version(VersionedPackage {
+        package: "aeson".into(),
+        version: "2.0".try_into().unwrap(),
+    });
+    assert_eq!(
+        bodies[&aeson],
+        vec![
+            "aeson-2.0 is out of bounds for:",
+            "",
+            "- [ ] baz-2. B <b@b.com>. Used by: library",
+            "- [ ] foo-1 (==1.*). @a. Used by: library, test-suite",
+            "- [ ] qux-3. No maintainer. Used by: library (>=1 && <1.5), benchmarks (<1.5)",
+        ]
+    );
+    assert_eq!(
+        bodies[&Culprit::Disabled("foo".into())],
+        vec![
+            "foo is disabled, which keeps out the following packages:",
+            "",
+            "- [ ] bar-1. @a. Used by: library",
+        ]
+    );
+}
@@ -0,0 +1,305 @@
+use crate::prelude::*;
+
+use serde::Serialize;
+
+use crate::sections;
+use crate::types::Package;
+
+#[derive(Debug, Clone, Copy, strum::EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Mode {
+    Text,
+    Json,
+}
+
+/// Sections where every entry should point to the issue explaining it.
+const EXPECT_REFERENCE: [&str; 1] = ["Stackage upper bounds"];
+
+pub fn issues_index(build_constraints: &Path, mode: Mode) {
+    let lines = sections::read(build_constraints);
+    let index = index(&lines);
+    match mode {
+        Mode::Text => print_index(&index),
+        Mode::Json => println!("{}", serde_json::to_string_pretty(&index).unwrap()),
+    }
+}
+
+/// An issue or pull request, `#123` being a stackage issue.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Reference {
+    repo: String,
+    number: u32,
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}#{}", self.repo, self.number)
+    }
+}
+
+/// The references in a comment, and whether each is marked `/closed`.
+/// Numbers too large to be an issue are skipped.
+fn references(comment: &str) -> Vec<(Reference, bool)> {
+    regex!(
+        r#"(?:https?://github\.com/([^/ ]+/[^/ ]+)/(?:issues|pull)/|(?:^|[^\w/])#)(\d+)(/closed)?"#
+    )
+    .captures_iter(comment)
+    .filter_map(|cap| {
+        let reference = Reference {
+            repo: cap
+                .get(1)
+                .map_or("commercialhaskell/stackage", |m| m.as_str())
+                .to_owned(),
+            number: cap[2].parse().ok()?,
+        };
+        Some((reference, cap.get(3).is_some()))
+    })
+    .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Citation {
+    /// 1-based
+    line: usize,
+    /// The section under `packages:`, or otherwise the top level key.
+    location: String,
+    /// The entry the reference is about, if any.
+    package: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct Cited {
+    reference: String,
+    closed: bool,
+    citations: Vec<Citation>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct Closed {
+    reference: String,
+    #[serde(flatten)]
+    citation: Citation,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+struct Index {
+    references: Vec<Cited>,
+    /// Entries citing a reference that is marked `/closed` somewhere.
+    closed: Vec<Closed>,
+    /// Entries in `EXPECT_REFERENCE` sections without a reference.
+    unreferenced: Vec<Citation>,
+}
+
+fn index(lines: &[String]) -> Index {
+    let mut cited: BTreeMap<Reference, (bool, Vec<Citation>)> = BTreeMap::new();
+    let mut unreferenced = vec![];
+    let mut location = String::new();
+    // References in comments on their own line apply to the entries
+    // below them, up to the next blank line or header.
+    let mut pending: Vec<(Reference, bool)> = vec![];
+
+    for (i, line) in lines.iter().enumerate() {
+        let mut cite = |reference: Reference, closed: bool, package: Option<&Package>| {
+            let (c, citations) = cited.entry(reference).or_default();
+            *c |= closed;
+            citations.push(Citation {
+                line: i + 1,
+                location: location.clone(),
+                package: package.map(|p| p.to_string()),
+            });
+        };
+
+        if line.trim().is_empty() {
+            pending.clear();
+        } else if let Some(key) = regex!(r#"^([^ #-][^:]*):"#).captures(line) {
+            location = key[1].to_owned();
+            pending.clear();
+        } else if let Some(name) = sections::parse_header(line) {
+            location = name;
+            pending.clear();
+        } else if let Some(comment) = line.trim_start().strip_prefix('#') {
+            for (reference, closed) in references(comment) {
+                cite(reference.clone(), closed, None);
+                pending.push((reference, closed));
+            }
+        } else if let Some((package, _, comment)) = sections::split_entry(line) {
+            let mut refs = references(&comment);
+            refs.extend(pending.iter().cloned());
+            if refs.is_empty() && EXPECT_REFERENCE.contains(&&*location) {
+                unreferenced.push(Citation {
+                    line: i + 1,
+                    location: location.clone(),
+                    package: Some(package.to_string()),
+                });
+            }
+            for (reference, closed) in refs {
+                cite(reference, closed, Some(&package));
+            }
+        }
+    }
+
+    let mut index = Index {
+        unreferenced,
+        ..Index::default()
+    };
+    for (reference, (closed, citations)) in cited {
+        let reference = reference.to_string();
+        if closed {
+            index.closed.extend(
+                citations
+                    .iter()
+                    .filter(|c| c.package.is_some())
+                    .map(|c| Closed {
+                        reference: reference.clone(),
+                        citation: c.clone(),
+                    }),
+            );
+        }
+        index.references.push(Cited {
+            reference,
+            closed,
+            citations,
+        });
+    }
+    index.closed.sort_by_key(|c| c.citation.line);
+    index
+}
+
+/// The references cited by each entry, by 1-based line number.
+pub(crate) fn entry_references(lines: &[String]) -> BTreeMap<usize, Vec<String>> {
+    let mut res: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    for Cited {
+        reference,
+        citations,
+        ..
+    } in index(lines).references
+    {
+        for c in citations.into_iter().filter(|c| c.package.is_some()) {
+            res.entry(c.line).or_default().push(reference.clone());
+        }
+    }
+    res
+}
+
+fn print_index(index: &Index) {
+    let citation = |c: &Citation| match &c.package {
+        Some(package) => format!("{}: {:?}: {package}", c.line, c.location),
+        None => format!("{}: {:?}", c.line, c.location),
+    };
+
+    for Cited {
+        reference,
+        closed,
+        citations,
+    } in &index.references
+    {
+        let closed = if *closed { " (closed)" } else { "" };
+        println!("{reference}{closed}");
+        for c in citations {
+            println!("    {}", citation(c));
+        }
+    }
+
+    if !index.closed.is_empty() {
+        println!("\nEntries citing closed issues:");
+        for Closed {
+            reference,
+            citation: c,
+        } in &index.closed
+        {
+            println!("    {} ({reference})", citation(c));
+        }
+    }
+
+    if !index.unreferenced.is_empty() {
+        println!("\nEntries without an issue reference:");
+        for c in &index.unreferenced {
+            println!("    {}", citation(c));
+        }
+    }
+}
+
+#[test]
+fn test_references() {
+    let stackage = |number| Reference {
+        repo: "commercialhaskell/stackage".to_owned(),
+        number,
+    };
+    assert_eq!(
+        references(" (#3494/closed, #5779)"),
+        vec![(stackage(3494), true), (stackage(5779), false)]
+    );
+    assert_eq!(
+        references(" https://github.com/commercialhaskell/stackage/issues/6122"),
+        vec![(stackage(6122), false)]
+    );
+    assert_eq!(
+        references(" see https://github.com/yesodweb/wai/pull/12#issuecomment-1"),
+        vec![(
+            Reference {
+                repo: "yesodweb/wai".to_owned(),
+                number: 12
+            },
+            false
+        )]
+    );
+    assert_eq!(references(" 0.10.0.0 compile fail"), vec![]);
+    assert_eq!(references(" #99999999999 #1"), vec![(stackage(1), false)]);
+}
+
+#[test]
+fn test_index() {
+    let lines: Vec<String> = [
+        "packages:",
+        "    # See #1/closed",
+        "    \"A @a\":",
+        "        - foo # #2/closed",
+        "        - bar # #3",
+        "    \"Stackage upper bounds\":",
+        "        # #4",
+        "        - baz < 2",
+        "        - qux < 3",
+        "",
+        "        - quux < 4",
+        "# end of packages",
+        "skipped-tests:",
+        "    - bar # #2",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let index = index(&lines);
+    let refs: Vec<(&str, bool, usize)> = index
+        .references
+        .iter()
+        .map(|c| (&*c.reference, c.closed, c.citations.len()))
+        .collect();
+    assert_eq!(
+        refs,
+        vec![
+            ("commercialhaskell/stackage#1", true, 1),
+            ("commercialhaskell/stackage#2", true, 2),
+            ("commercialhaskell/stackage#3", false, 1),
+            ("commercialhaskell/stackage#4", false, 3),
+        ]
+    );
+    let closed: Vec<(&str, &str)> = index
+        .closed
+        .iter()
+        .map(|c| {
+            (
+                &*c.citation.location,
+                c.citation.package.as_deref().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(closed, vec![("A @a", "foo"), ("skipped-tests", "bar")]);
+    assert_eq!(
+        index.unreferenced,
+        vec![Citation {
+            line: 11,
+            location: "Stackage upper bounds".to_owned(),
+            package: Some("quux".to_owned()),
+        }]
+    );
+}
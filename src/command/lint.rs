@@ -0,0 +1,290 @@
+use crate::prelude::*;
+
+use crate::build_constraints::SPECIAL_SECTIONS;
+use crate::handle::{bounds_blocks, Location};
+use crate::regex::*;
+use crate::sections::{self, Section};
+use crate::types::Package;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Violation {
+    /// 0-based line index
+    line: usize,
+    message: String,
+}
+
+struct Rule {
+    id: &'static str,
+    severity: Severity,
+    check: fn(&[String]) -> Vec<Violation>,
+    fix: Option<fn(&mut [String])>,
+}
+
+const RULES: &[Rule] = &[
+    Rule {
+        id: "compilation-failures-disabled",
+        severity: Severity::Error,
+        check: |lines| missing_lt0(lines, "Compilation failures"),
+        fix: Some(|lines| add_lt0(lines, "Compilation failures")),
+    },
+    Rule {
+        id: "removed-packages-disabled",
+        severity: Severity::Error,
+        check: |lines| missing_lt0(lines, "Removed packages"),
+        fix: Some(|lines| add_lt0(lines, "Removed packages")),
+    },
+    Rule {
+        id: "bounds-sorted",
+        severity: Severity::Warning,
+        check: unsorted_bounds,
+        fix: Some(sort_bounds),
+    },
+    Rule {
+        id: "single-maintainer-section",
+        severity: Severity::Warning,
+        check: multiple_sections,
+        fix: None,
+    },
+    Rule {
+        id: "ghc-major-version",
+        severity: Severity::Error,
+        check: ghc_major_version,
+        fix: Some(fix_ghc_major_version),
+    },
+];
+
+/// Checks build-constraints invariants, exiting with an error if any
+/// rule with severity error is violated, or any rule at all with
+/// `deny_warnings`.
+pub fn lint(build_constraints: &Path, fix: bool, deny_warnings: bool) {
+    let mut lines = sections::read(build_constraints);
+
+    if fix {
+        for rule in RULES {
+            if let Some(f) = rule.fix {
+                if !(rule.check)(&lines).is_empty() {
+                    println!("Fixing {}", rule.id);
+                    f(&mut lines);
+                }
+            }
+        }
+        fs::write_lines(build_constraints, lines.clone());
+    }
+
+    let mut errors = 0;
+    let mut violations: Vec<(usize, &Rule, String)> = vec![];
+    for rule in RULES {
+        for Violation { line, message } in (rule.check)(&lines) {
+            if rule.severity == Severity::Error {
+                errors += 1;
+            }
+            violations.push((line, rule, message));
+        }
+    }
+    violations.sort_by_key(|(line, _, _)| *line);
+    for (line, rule, message) in &violations {
+        println!(
+            "{file}:{line}: {severity}[{id}]: {message}",
+            file = build_constraints.display(),
+            line = line + 1,
+            severity = rule.severity,
+            id = rule.id,
+        );
+    }
+
+    if errors > 0 || (deny_warnings && !violations.is_empty()) {
+        std::process::exit(1);
+    }
+}
+
+fn find_section<'a>(sections: &'a [Section], name: &str) -> Option<&'a Section> {
+    sections.iter().find(|s| s.name == name)
+}
+
+fn missing_lt0(lines: &[String], section: &str) -> Vec<Violation> {
+    let sections = sections::sections(lines);
+    let Some(section) = find_section(&sections, section) else {
+        return vec![];
+    };
+    section
+        .entries
+        .iter()
+        .filter(|e| !regex!(r#"^ +- +[^ #]+ *< *0\b"#).is_match(&lines[e.line]))
+        .map(|e| Violation {
+            line: e.line,
+            message: format!("{} must be disabled with < 0", e.package),
+        })
+        .collect()
+}
+
+/// Adds `< 0` to entries that have no bound at all.
+fn add_lt0(lines: &mut [String], section: &str) {
+    let sections = sections::sections(lines);
+    let Some(section) = find_section(&sections, section) else {
+        return;
+    };
+    for entry in &section.entries {
+        let line = &lines[entry.line];
+        if let Ok(cap) = Captures::new(regex!(r#"^( +- +[^ #]+)( *#.*)?$"#), line) {
+            let package: String = cap.get(1).unwrap();
+            let comment: String = cap.get(2).unwrap_or_default();
+            lines[entry.line] = format!("{package} < 0{comment}");
+        }
+    }
+}
+
+fn unsorted_bounds(lines: &[String]) -> Vec<Violation> {
+    let mut violations = vec![];
+    for (loc, range) in bounds_blocks(lines) {
+        if let Some(i) = sections::first_unsorted(&lines[range.clone()], str::to_owned) {
+            violations.push(Violation {
+                line: range.start + i,
+                message: format!("{} bounds block is not sorted", location_name(loc)),
+            });
+        }
+    }
+    violations
+}
+
+fn sort_bounds(lines: &mut [String]) {
+    for (_, range) in bounds_blocks(lines) {
+        sections::sort_entries(&mut lines[range], str::to_owned);
+    }
+}
+
+fn location_name(loc: Location) -> &'static str {
+    match loc {
+        Location::Lib => "Library and exe",
+        Location::Test => "Test",
+        Location::Bench => "Benchmark",
+    }
+}
+
+fn multiple_sections(lines: &[String]) -> Vec<Violation> {
+    let mut seen: BTreeMap<Package, String> = BTreeMap::new();
+    let mut violations = vec![];
+    for section in sections::sections(lines) {
+        if SPECIAL_SECTIONS.contains(&&*section.name) {
+            continue;
+        }
+        for entry in section.entries {
+            match seen.get(&entry.package) {
+                Some(first) if *first != section.name => violations.push(Violation {
+                    line: entry.line,
+                    message: format!("{} is also listed under {first:?}", entry.package),
+                }),
+                Some(_) => {}
+                None => {
+                    seen.insert(entry.package, section.name.clone());
+                }
+            }
+        }
+    }
+    violations
+}
+
+fn top_level_value(lines: &[String], key: &str) -> Option<(usize, String)> {
+    lines.iter().enumerate().find_map(|(i, line)| {
+        let value = line.strip_prefix(key)?.strip_prefix(':')?;
+        Some((i, value.trim().trim_matches('"').to_owned()))
+    })
+}
+
+fn expected_major_version(ghc_version: &str) -> String {
+    ghc_version.split('.').take(2).collect::<Vec<_>>().join(".")
+}
+
+fn ghc_major_version(lines: &[String]) -> Vec<Violation> {
+    let (Some((_, version)), Some((line, major))) = (
+        top_level_value(lines, "ghc-version"),
+        top_level_value(lines, "ghc-major-version"),
+    ) else {
+        return vec![];
+    };
+    let expected = expected_major_version(&version);
+    if major == expected {
+        vec![]
+    } else {
+        vec![Violation {
+            line,
+            message: format!("ghc-major-version is {major} but ghc-version is {version}"),
+        }]
+    }
+}
+
+fn fix_ghc_major_version(lines: &mut [String]) {
+    if let (Some((_, version)), Some((line, _))) = (
+        top_level_value(lines, "ghc-version"),
+        top_level_value(lines, "ghc-major-version"),
+    ) {
+        lines[line] = format!(
+            "ghc-major-version: \"{}\"",
+            expected_major_version(&version)
+        );
+    }
+}
+
+#[test]
+fn test_rules() {
+    let mut lines: Vec<String> = [
+        "ghc-major-version: \"9.0\"",
+        "ghc-version: \"9.2.1\"",
+        "packages:",
+        "    \"A\":",
+        "        - foo",
+        "    \"B\":",
+        "        - foo",
+        "    \"Removed packages\":",
+        "        - bar < 0",
+        "        - baz # gone",
+        "    \"Library and exe bounds failures\":",
+        "        - b < 0 # tried b-1",
+        "        - a < 0 # tried a-1",
+        "    # End of Library and exe bounds failures",
+        "# end of packages",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+
+    let lines_of = |lines: &[String]| -> Vec<(&str, usize)> {
+        RULES
+            .iter()
+            .flat_map(|r| (r.check)(lines).into_iter().map(move |v| (r.id, v.line)))
+            .collect()
+    };
+    assert_eq!(
+        lines_of(&lines),
+        vec![
+            ("removed-packages-disabled", 9),
+            ("bounds-sorted", 12),
+            ("single-maintainer-section", 6),
+            ("ghc-major-version", 0),
+        ]
+    );
+
+    for rule in RULES {
+        if let Some(f) = rule.fix {
+            f(&mut lines);
+        }
+    }
+    assert_eq!(lines[0], "ghc-major-version: \"9.2\"");
+    assert_eq!(lines[9], "        - baz < 0 # gone");
+    assert_eq!(lines[11], "        - a < 0 # tried a-1");
+    assert_eq!(lines_of(&lines), vec![("single-maintainer-section", 6)]);
+}
@@ -0,0 +1,359 @@
+use crate::prelude::*;
+
+use std::ops::Range;
+
+use crate::build_constraints::{Maintainer, SPECIAL_SECTIONS};
+use crate::handle::{bounds_blocks, Location};
+use crate::latest_version::latest_version;
+use crate::sections;
+use crate::snapshot::SnapshotYaml;
+use crate::types::{Package, Version};
+use crate::yaml;
+
+/// Top level lists that are reported for each package.
+const LISTS: [&str; 6] = [
+    "skipped-tests",
+    "expected-test-failures",
+    "skipped-benchmarks",
+    "expected-benchmark-failures",
+    "skipped-haddocks",
+    "expected-haddock-failures",
+];
+
+/// Prints the status of every package of the maintainer matching
+/// `query`, or with `all` writes a markdown digest per maintainer to
+/// `out_dir`.
+pub fn maintainer(
+    build_constraints: &Path,
+    query: Option<&str>,
+    all: bool,
+    out_dir: Option<&Path>,
+    snapshot: Option<&Path>,
+    no_hackage: bool,
+) {
+    let lines = sections::read(build_constraints);
+    let maintainers = maintainer_sections(&lines);
+
+    let maintainers: Vec<_> = if all {
+        maintainers
+    } else {
+        let query = query.unwrap();
+        let matching: Vec<_> = maintainers
+            .into_iter()
+            .filter(|(m, _)| matches(query, m))
+            .collect();
+        if matching.is_empty() {
+            eprintln!("ERROR: No maintainer section matches {query:?}");
+            std::process::exit(1);
+        }
+        matching
+    };
+
+    let snapshot = snapshot_versions(snapshot);
+    let hackage: BTreeMap<Package, Version> = if no_hackage {
+        BTreeMap::new()
+    } else {
+        latest_version(
+            maintainers
+                .iter()
+                .flat_map(|(_, e)| e.iter().map(|(p, _)| p)),
+        )
+    };
+    let index = Index::new(&lines);
+    let status = |entries: &[(Package, String)]| -> Vec<Status> {
+        entries
+            .iter()
+            .map(|(package, bound)| index.status(package, bound, &hackage, &snapshot))
+            .collect()
+    };
+
+    match out_dir {
+        Some(out_dir) if all => {
+            fs::create_dir_all(out_dir).unwrap();
+            let mut digests: BTreeMap<String, (Vec<&Maintainer>, Vec<Status>)> = BTreeMap::new();
+            for (m, entries) in &maintainers {
+                let (headers, statuses) = digests.entry(file_name(m)).or_default();
+                headers.push(m);
+                statuses.extend(status(entries));
+            }
+            for (name, (headers, statuses)) in digests {
+                let path = out_dir.join(format!("{name}.md"));
+                fs::write_lines(&path, markdown(&headers, &statuses));
+            }
+        }
+        _ => {
+            for (m, entries) in &maintainers {
+                println!("{m}:");
+                for s in status(entries) {
+                    print_status(&s);
+                }
+            }
+        }
+    }
+}
+
+/// The maintainer sections in file order, with the package and bound of
+/// each entry.
+pub(crate) fn maintainer_sections(lines: &[String]) -> Vec<(Maintainer, Vec<(Package, String)>)> {
+    sections::sections(lines)
+        .into_iter()
+        .filter(|s| !SPECIAL_SECTIONS.contains(&&*s.name))
+        .map(|s| {
+            let entries = s
+                .entries
+                .iter()
+                .filter_map(|e| sections::split_entry(&lines[e.line]))
+                .map(|(package, bound, _)| (package, bound))
+                .collect();
+            (Maintainer::parse(&s.name), entries)
+        })
+        .collect()
+}
+
+pub(crate) fn snapshot_versions(snapshot: Option<&Path>) -> BTreeMap<Package, Version> {
+    let Some(path) = snapshot else {
+        return BTreeMap::new();
+    };
+    let snapshot: SnapshotYaml = yaml::yaml_from_file(path)
+        .unwrap_or_else(|e| panic!("Error reading snapshot {path:?}: {e}"));
+    snapshot
+        .packages
+        .into_iter()
+        .map(|p| (p.hackage.0.package, p.hackage.0.version))
+        .collect()
+}
+
+fn matches(query: &str, m: &Maintainer) -> bool {
+    let eq = |s: &String| s.eq_ignore_ascii_case(query);
+    if query.starts_with('@') {
+        m.handles.iter().any(eq)
+    } else if query.contains('@') {
+        m.emails.iter().any(eq)
+    } else {
+        eq(&m.header) || eq(&m.name)
+    }
+}
+
+/// The github handle if there is one, or else the name, for use as a
+/// file name. Sections of the same person with the same handle share a
+/// digest.
+pub(crate) fn file_name(m: &Maintainer) -> String {
+    let name = match m.handles.first() {
+        Some(handle) => handle.trim_start_matches('@').to_lowercase(),
+        None => m.name.to_lowercase(),
+    };
+    regex!(r#"[^a-z0-9_.-]+"#)
+        .replace_all(&name, "-")
+        .trim_matches('-')
+        .to_owned()
+}
+
+pub(crate) struct Status {
+    pub(crate) package: Package,
+    pub(crate) bound: String,
+    /// Reasons from the special sections that disable the package.
+    pub(crate) disabled: Vec<String>,
+    /// Comments of the generated test and benchmark bounds, one per
+    /// component.
+    pub(crate) tests: Vec<String>,
+    pub(crate) benches: Vec<String>,
+    pub(crate) lists: Vec<&'static str>,
+    pub(crate) hackage: Option<Version>,
+    pub(crate) snapshot: Option<Version>,
+}
+
+impl Status {
+    pub(crate) fn is_disabled(&self) -> bool {
+        !self.disabled.is_empty() || regex!(r#"^< *0\b"#).is_match(&self.bound)
+    }
+}
+
+/// Everything outside of the maintainer sections that is said about
+/// each package.
+#[derive(Default)]
+pub(crate) struct Index {
+    disabled: BTreeMap<Package, Vec<String>>,
+    tests: BTreeMap<Package, Vec<String>>,
+    benches: BTreeMap<Package, Vec<String>>,
+    lists: BTreeMap<Package, Vec<&'static str>>,
+}
+
+impl Index {
+    pub(crate) fn new(lines: &[String]) -> Index {
+        let mut index = Index::default();
+        for section in sections::sections(lines) {
+            if !SPECIAL_SECTIONS.contains(&&*section.name) {
+                continue;
+            }
+            for entry in &section.entries {
+                let Some((package, bound, comment)) = sections::split_entry(&lines[entry.line])
+                else {
+                    continue;
+                };
+                if regex!(r#"^< *0\b"#).is_match(&bound) {
+                    let reason = if comment.is_empty() {
+                        section.name.clone()
+                    } else {
+                        format!("{}: {comment}", section.name)
+                    };
+                    index.disabled.entry(package).or_default().push(reason);
+                }
+            }
+        }
+
+        let blocks = bounds_blocks(lines);
+        for (loc, range) in &blocks {
+            let map = match loc {
+                Location::Lib => continue,
+                Location::Test => &mut index.tests,
+                Location::Bench => &mut index.benches,
+            };
+            for line in &lines[range.clone()] {
+                if let Some((package, _, comment)) = sections::split_entry(line) {
+                    map.entry(package).or_default().push(comment);
+                }
+            }
+        }
+
+        let generated: Vec<&Range<usize>> = blocks.iter().map(|(_, r)| r).collect();
+        for key in LISTS {
+            for (i, package) in sections::list_items(lines, key) {
+                if !generated.iter().any(|r| r.contains(&i)) {
+                    index.lists.entry(package).or_default().push(key);
+                }
+            }
+        }
+        index
+    }
+
+    pub(crate) fn status(
+        &self,
+        package: &Package,
+        bound: &str,
+        hackage: &BTreeMap<Package, Version>,
+        snapshot: &BTreeMap<Package, Version>,
+    ) -> Status {
+        Status {
+            package: package.clone(),
+            bound: bound.to_owned(),
+            disabled: self.disabled.get(package).cloned().unwrap_or_default(),
+            tests: self.tests.get(package).cloned().unwrap_or_default(),
+            benches: self.benches.get(package).cloned().unwrap_or_default(),
+            lists: self.lists.get(package).cloned().unwrap_or_default(),
+            hackage: hackage.get(package).cloned(),
+            snapshot: snapshot.get(package).cloned(),
+        }
+    }
+}
+
+fn print_status(s: &Status) {
+    if s.bound.is_empty() {
+        println!("    {}", s.package);
+    } else {
+        println!("    {} {}", s.package, s.bound);
+    }
+    for reason in &s.disabled {
+        println!("        disabled: {reason}");
+    }
+    for tests in &s.tests {
+        println!("        tests disabled: {tests}");
+    }
+    for benches in &s.benches {
+        println!("        benchmarks disabled: {benches}");
+    }
+    for list in &s.lists {
+        println!("        in {list}");
+    }
+    let version = |v: &Option<Version>| v.as_ref().map_or("-".to_owned(), |v| v.to_string());
+    if s.hackage.is_some() || s.snapshot.is_some() {
+        println!(
+            "        hackage: {}, snapshot: {}",
+            version(&s.hackage),
+            version(&s.snapshot)
+        );
+    }
+}
+
+fn markdown(headers: &[&Maintainer], statuses: &[Status]) -> Vec<String> {
+    let cell = |s: &str| s.replace('|', "\\|");
+    let version = |v: &Option<Version>| v.as_ref().map_or(String::new(), |v| v.to_string());
+
+    let mut out = vec![];
+    for m in headers {
+        out.push(format!("# {}", cell(&m.header)));
+    }
+    out.push(String::new());
+    let disabled = statuses.iter().filter(|s| s.is_disabled()).count();
+    out.push(format!(
+        "{} packages, {disabled} disabled, {tests} with disabled tests, {benches} with disabled benchmarks",
+        statuses.len(),
+        tests = statuses.iter().filter(|s| !s.tests.is_empty()).count(),
+        benches = statuses.iter().filter(|s| !s.benches.is_empty()).count(),
+    ));
+    out.push(String::new());
+    out.push(
+        "| Package | Bound | Disabled | Tests | Benchmarks | Listed in | Hackage | Snapshot |"
+            .to_owned(),
+    );
+    out.push("|---|---|---|---|---|---|---|---|".to_owned());
+    for s in statuses {
+        out.push(format!(
+            "| {package} | {bound} | {disabled} | {tests} | {benches} | {lists} | {hackage} | {snapshot} |",
+            package = s.package,
+            bound = cell(&s.bound),
+            disabled = cell(&s.disabled.join("; ")),
+            tests = cell(&s.tests.join("; ")),
+            benches = cell(&s.benches.join("; ")),
+            lists = s.lists.join(", "),
+            hackage = version(&s.hackage),
+            snapshot = version(&s.snapshot),
+        ));
+    }
+    out
+}
+
+#[test]
+fn test_index() {
+    let lines: Vec<String> = [
+        "packages:",
+        "    \"A <a@a.com> @a\":",
+        "        - foo",
+        "        - bar < 2",
+        "    \"Library and exe bounds failures\":",
+        "        - foo < 0 # tried foo-1, but its *library* does not support: base-5",
+        "    # End of Library and exe bounds failures",
+        "# end of packages",
+        "skipped-tests:",
+        "    - bar",
+        "    # Test bounds issues",
+        "    - foo # tried foo-1, but its *test-suite* does not support: hspec-3",
+        "    - foo # tried foo-1, but its *test-suite* does not support: QuickCheck-3",
+        "    # End of Test bounds issues",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let index = Index::new(&lines);
+    let foo = Package::from("foo");
+    let bar = Package::from("bar");
+    assert_eq!(
+        index.disabled[&foo],
+        vec!["Library and exe bounds failures: tried foo-1, but its *library* does not support: base-5"]
+    );
+    assert_eq!(
+        index.tests[&foo],
+        vec![
+            "tried foo-1, but its *test-suite* does not support: hspec-3",
+            "tried foo-1, but its *test-suite* does not support: QuickCheck-3",
+        ]
+    );
+    assert_eq!(index.lists[&bar], vec!["skipped-tests"]);
+    assert!(!index.lists.contains_key(&foo));
+
+    let m = Maintainer::parse("A <a@a.com> @a");
+    assert!(matches("@A", &m));
+    assert!(matches("a@a.com", &m));
+    assert!(matches("A", &m));
+    assert!(!matches("@b", &m));
+    assert_eq!(file_name(&m), "a");
+}
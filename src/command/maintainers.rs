@@ -1,14 +1,152 @@
 use crate::prelude::*;
 
-use crate::build_constraints::{self, Maintenance};
+use crate::build_constraints::{self, Maintainer, Maintenance};
+use crate::command::move_packages::move_impl;
+use crate::sections;
 
-pub fn maintainers(build_constraints: &Path) {
+pub fn maintainers(build_constraints: &Path, fix: bool) {
     let bc = build_constraints::parse(build_constraints);
-    for maintainer in bc.maintainers() {
-        if let Maintenance::Maintainer(maintainer) = maintainer {
-            if maintainer.github_users().next().is_none() {
-                println!("{maintainer}: Missing github handle")
+    let maintainers: Vec<&Maintainer> = bc.maintainers().filter_map(|m| m.maintainer()).collect();
+
+    for maintainer in &maintainers {
+        for problem in maintainer.problems() {
+            println!("{maintainer}: {problem}");
+        }
+    }
+
+    let duplicates = duplicates(&maintainers);
+    for group in &duplicates {
+        println!(
+            "Same person in several sections: {}",
+            group
+                .iter()
+                .map(|m| format!("{:?}", m.header))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    if fix && !duplicates.is_empty() {
+        let mut lines = sections::read(build_constraints);
+        for group in duplicates {
+            let target = group
+                .iter()
+                .min_by_key(|m| {
+                    let count = bc
+                        .packages
+                        .get(&Maintenance::Maintainer((**m).clone()))
+                        .map_or(0, |p| p.len());
+                    (m.problems().len(), std::cmp::Reverse(count))
+                })
+                .unwrap();
+            for source in group.iter().filter(|m| m.header != target.header) {
+                println!("Merging {:?} into {:?}", source.header, target.header);
+                merge(&mut lines, &source.header, &target.header);
             }
         }
+        fs::write_lines(build_constraints, lines);
     }
 }
+
+/// Moves all entries of one section into another and removes the
+/// emptied section. Entries already in `target` are not duplicated.
+fn merge(lines: &mut Vec<String>, source: &str, target: &str) {
+    let all: BTreeSet<_> = sections::sections(lines)
+        .into_iter()
+        .find(|s| s.name == source)
+        .map(|s| s.entries.into_iter().map(|e| e.package).collect())
+        .unwrap_or_default();
+    move_impl(lines, &all, target, |s| s == source);
+    sections::remove_section(lines, source);
+}
+
+/// Groups of single-person headers that share an email address or
+/// github handle.
+fn duplicates<'a>(maintainers: &[&'a Maintainer]) -> Vec<Vec<&'a Maintainer>> {
+    let people: Vec<&Maintainer> = maintainers
+        .iter()
+        .copied()
+        .filter(|m| m.is_single_person())
+        .collect();
+
+    let keys = |m: &Maintainer| -> BTreeSet<String> {
+        m.emails
+            .iter()
+            .chain(m.handles.iter())
+            .map(|k| k.to_lowercase())
+            .collect()
+    };
+
+    let mut group_of: Vec<usize> = (0..people.len()).collect();
+    let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+    for (i, m) in people.iter().enumerate() {
+        for key in keys(m) {
+            match seen.get(&key) {
+                Some(&j) => {
+                    let (from, to) = (group_of[i], group_of[j]);
+                    for g in group_of.iter_mut() {
+                        if *g == from {
+                            *g = to;
+                        }
+                    }
+                }
+                None => {
+                    seen.insert(key, i);
+                }
+            }
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<&Maintainer>> = BTreeMap::new();
+    for (i, m) in people.into_iter().enumerate() {
+        groups.entry(group_of[i]).or_default().push(m);
+    }
+    groups.into_values().filter(|g| g.len() >= 2).collect()
+}
+
+#[test]
+fn test_duplicates() {
+    let a = Maintainer::parse("Adam Bergmark <adam@bergmark.nl> @bergmark");
+    let b = Maintainer::parse("Adam Bergmark @bergmark");
+    let c = Maintainer::parse("A. Bergmark <adam@bergmark.nl>");
+    let d = Maintainer::parse("Adam Bergmark @bergmark & Someone Else @someone");
+    let e = Maintainer::parse("Someone Else @someone");
+    let groups = duplicates(&[&a, &b, &c, &d, &e]);
+    assert_eq!(groups, vec![vec![&a, &b, &c]]);
+}
+
+#[test]
+fn test_merge() {
+    let mut lines: Vec<String> = [
+        "packages:",
+        "    \"Adam Bergmark @bergmark\":",
+        "        - bar",
+        "        - foo",
+        "",
+        "    \"Adam Bergmark <adam@bergmark.nl> @bergmark\":",
+        "        - baz",
+        "        - foo",
+        "",
+        "# end of packages",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    merge(
+        &mut lines,
+        "Adam Bergmark @bergmark",
+        "Adam Bergmark <adam@bergmark.nl> @bergmark",
+    );
+    assert_eq!(
+        lines,
+        vec![
+            "packages:",
+            "    \"Adam Bergmark <adam@bergmark.nl> @bergmark\":",
+            "        - bar",
+            "        - baz",
+            "        - foo",
+            "",
+            "# end of packages",
+        ]
+    );
+}
@@ -0,0 +1,130 @@
+use crate::prelude::*;
+
+use crate::build_constraints::SPECIAL_SECTIONS;
+use crate::sections;
+use crate::types::Package;
+
+/// Special sections whose entries are memberships rather than bounds, so
+/// they can be moved to a maintainer. Entries in the other special
+/// sections are left alone.
+const MOVABLE: [&str; 2] = ["Grandfathered dependencies", "Abandoned packages"];
+
+fn movable(section: &str) -> bool {
+    !SPECIAL_SECTIONS.contains(&section) || MOVABLE.contains(&section)
+}
+
+pub fn move_packages(build_constraints: &Path, packages: &[String], to: &str) {
+    let mut lines = sections::read(build_constraints);
+    if !sections::sections(&lines).iter().any(|s| s.name == to) {
+        eprintln!("ERROR: Could not find section {to:?}");
+        std::process::exit(1);
+    }
+    let packages: BTreeSet<Package> = packages.iter().map(|p| Package::from(&**p)).collect();
+    let found: BTreeSet<Package> = sections::sections(&lines)
+        .into_iter()
+        .filter(|s| s.name == to || movable(&s.name))
+        .flat_map(|s| s.entries.into_iter().map(|e| e.package))
+        .collect();
+    for package in packages.difference(&found) {
+        println!("WARN: {package} was not found in any section it can be moved from");
+    }
+    move_impl(&mut lines, &packages, to, |_| true);
+    fs::write_lines(build_constraints, lines);
+}
+
+/// Moves every entry for the packages in the maintainer sections (and
+/// `MOVABLE`) accepted by `from` into `to`, keeping bounds and comments.
+/// Entries that are identical to one already in `to` are dropped.
+pub(crate) fn move_impl(
+    lines: &mut Vec<String>,
+    packages: &BTreeSet<Package>,
+    to: &str,
+    from: impl Fn(&str) -> bool,
+) {
+    let sources: Vec<String> = sections::sections(lines)
+        .into_iter()
+        .filter(|s| s.name != to && movable(&s.name) && from(&s.name))
+        .filter(|s| s.entries.iter().any(|e| packages.contains(&e.package)))
+        .map(|s| s.name)
+        .collect();
+
+    for source in sources {
+        for (package, block) in sections::remove(lines, &source, packages) {
+            let entry = block.last().unwrap().trim();
+            let existing: Vec<String> = {
+                let sections = sections::sections(lines);
+                let target = sections.iter().find(|s| s.name == to).unwrap();
+                target
+                    .entries
+                    .iter()
+                    .filter(|e| e.package == package)
+                    .map(|e| lines[e.line].trim().to_owned())
+                    .collect()
+            };
+            if existing.iter().any(|e| e == entry) {
+                println!("{package}: dropped duplicate entry from {source:?}");
+                continue;
+            }
+            if !existing.is_empty() {
+                println!("WARN: {package} is now listed several times in {to:?}");
+            }
+            println!("{package}: {source:?} -> {to:?}");
+            sections::insert_block_sorted(lines, to, &package, block);
+        }
+    }
+}
+
+#[test]
+fn test_move_impl() {
+    let mut lines: Vec<String> = [
+        "packages:",
+        "    \"A\":",
+        "        - bar < 2 # bound",
+        "        - foo",
+        "",
+        "    \"B\":",
+        "        - foo",
+        "        - zoo",
+        "",
+        "    \"Library and exe bounds failures\":",
+        "        - bar < 0 # tried bar-2",
+        "",
+        "    \"Stackage upper bounds\":",
+        "        - foo < 1.2",
+        "",
+        "    \"Abandoned packages\":",
+        "        - qux",
+        "# end of packages",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    move_impl(
+        &mut lines,
+        &BTreeSet::from(["bar".into(), "foo".into(), "qux".into()]),
+        "B",
+        |_| true,
+    );
+    assert_eq!(
+        lines,
+        vec![
+            "packages:",
+            "    \"A\": []",
+            "",
+            "    \"B\":",
+            "        - bar < 2 # bound",
+            "        - foo",
+            "        - qux",
+            "        - zoo",
+            "",
+            "    \"Library and exe bounds failures\":",
+            "        - bar < 0 # tried bar-2",
+            "",
+            "    \"Stackage upper bounds\":",
+            "        - foo < 1.2",
+            "",
+            "    \"Abandoned packages\": []",
+            "# end of packages",
+        ]
+    );
+}
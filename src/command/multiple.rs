@@ -1,17 +1,52 @@
 use crate::prelude::*;
 
-use crate::build_constraints;
+use crate::build_constraints::{self, SPECIAL_SECTIONS};
+use crate::command::move_packages;
+use crate::sections;
 
-pub fn multiple(build_constraints: &Path) {
+#[derive(Debug, Clone, Copy, strum::EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Resolve {
+    KeepFirst,
+    KeepLast,
+}
+
+pub fn multiple(build_constraints: &Path, resolve: Option<Resolve>) {
     let bc = build_constraints::parse(build_constraints).by_package();
-    for (package, bc) in bc.packages {
+    for (package, bc) in &bc.packages {
         let maintainers: Vec<_> = bc
             .maintainers
-            .into_iter()
+            .iter()
             .filter_map(|m| m.maintainer().map(|m| m.to_string()))
             .collect();
         if maintainers.len() >= 2 {
             println!("{package}: {}", maintainers.join(", "));
         }
     }
+
+    if let Some(resolve) = resolve {
+        let mut lines = sections::read(build_constraints);
+        for (package, bc) in bc.packages {
+            if bc.maintainers.iter().filter_map(|m| m.maintainer()).count() < 2 {
+                continue;
+            }
+            // Sections in file order, rather than sorted by name
+            let in_sections: Vec<String> = sections::sections(&lines)
+                .into_iter()
+                .filter(|s| !SPECIAL_SECTIONS.contains(&&*s.name))
+                .filter(|s| s.entries.iter().any(|e| e.package == package))
+                .map(|s| s.name)
+                .collect();
+            let keep = match resolve {
+                Resolve::KeepFirst => in_sections.first(),
+                Resolve::KeepLast => in_sections.last(),
+            };
+            if let Some(keep) = keep {
+                move_packages::move_impl(&mut lines, &BTreeSet::from([package]), keep, |s| {
+                    !SPECIAL_SECTIONS.contains(&s)
+                });
+            }
+        }
+        fs::write_lines(build_constraints, lines);
+    }
 }
@@ -1,9 +1,9 @@
 use crate::prelude::*;
 
+use crate::bounds_failure::BoundsFailure;
 use crate::handle::{handle, DisabledPackage};
 use crate::ignores::Ignores;
 use crate::latest_version::latest_version;
-use crate::regex::*;
 use crate::types::*;
 
 pub fn outdated(build_constraints: &Path, ignore_file: Option<&Path>, show_lines: bool) {
@@ -28,16 +28,14 @@ pub fn outdated(build_constraints: &Path, ignore_file: Option<&Path>, show_lines
         map.insert(package, VersionTag::Manual(version));
     }
     let mut support: BTreeMap<(Package, Version), BTreeSet<(Package, Version)>> = BTreeMap::new();
-    for v in all.into_iter() {
-        let cap = Captures::new(regex!("tried ([^ ]+)-([^,-]+),"), &v).unwrap();
-        let package: Package = cap.get(1).unwrap();
-        let version: Version = cap.try_get(2).unwrap();
+    for BoundsFailure { tried, cause, .. } in all.iter().filter_map(|v| BoundsFailure::parse(v)) {
+        let VersionedPackage { package, version } = tried;
         map.insert(package.clone(), VersionTag::Auto(version.clone()));
 
-        if let Ok(cap) = Captures::new(regex!("does not support: ([^ ]+)-([^-]+)"), &v) {
-            let dep_package = cap.get(1).unwrap();
-            let dep_version = cap.try_get(2).unwrap();
-            let entry = support.entry((dep_package, dep_version)).or_default();
+        if let Some(dep) = cause.snapshot_version() {
+            let entry = support
+                .entry((dep.package.clone(), dep.version.clone()))
+                .or_default();
             entry.insert((package, version));
         }
     }
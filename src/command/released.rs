@@ -0,0 +1,132 @@
+use crate::prelude::*;
+
+use crate::latest_version::latest_version;
+use crate::regex::*;
+use crate::sections;
+use crate::types::{Package, Version, VersionedPackage};
+
+const KEY: &str = "tell-me-when-its-released";
+
+/// Prints the `tell-me-when-its-released` entries that have a newer
+/// version on Hackage (from pantry) with their comments, exiting with an
+/// error if there are any.
+pub fn released(build_constraints: &Path) {
+    let lines = sections::read(build_constraints);
+    let watched = watched(&lines);
+    let latest = latest_version(watched.iter().map(|w| &w.versioned.package));
+
+    let fired = fired(&watched, &latest);
+    for (w, latest) in &fired {
+        let comment = w
+            .comment
+            .as_ref()
+            .map(|c| format!(": {c}"))
+            .unwrap_or_default();
+        println!(
+            "{file}:{line}: {versioned} has been released as {latest}{comment}",
+            file = build_constraints.display(),
+            line = w.line + 1,
+            versioned = w.versioned,
+        );
+    }
+
+    if !fired.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Watched {
+    /// 0-based line index
+    line: usize,
+    versioned: VersionedPackage,
+    comment: Option<String>,
+}
+
+fn watched(lines: &[String]) -> Vec<Watched> {
+    let Some(range) = sections::top_level(lines, KEY) else {
+        return vec![];
+    };
+    range
+        .filter_map(|line| {
+            let cap =
+                Captures::new(regex!(r#"^ *- +([^ #]+) *(?:# *(.*?))? *$"#), &lines[line]).ok()?;
+            let versioned = match VersionedPackage::try_from(cap.get::<String>(1).unwrap()) {
+                Ok(versioned) => versioned,
+                Err(_) => {
+                    eprintln!(
+                        "ERROR: Expected package-version in {KEY} on line {}: {}",
+                        line + 1,
+                        lines[line]
+                    );
+                    std::process::exit(1);
+                }
+            };
+            Some(Watched {
+                line,
+                versioned,
+                comment: cap.get(2).ok().filter(|c: &String| !c.is_empty()),
+            })
+        })
+        .collect()
+}
+
+/// The entries where pantry has a newer version, with that version.
+fn fired<'a>(
+    watched: &'a [Watched],
+    latest: &BTreeMap<Package, Version>,
+) -> Vec<(&'a Watched, Version)> {
+    watched
+        .iter()
+        .filter_map(|w| {
+            let latest = latest.get(&w.versioned.package)?;
+            (latest > &w.versioned.version).then(|| (w, latest.clone()))
+        })
+        .collect()
+}
+
+#[test]
+fn test_fired() {
+    let lines: Vec<String> = [
+        "packages:",
+        "    \"A @a\":",
+        "        - foo",
+        "# end of packages",
+        "tell-me-when-its-released:",
+        "- foo-1.0 # Remove the bound on foo in \"A @a\"",
+        "  - bar-2.0.1",
+        "- baz-3 #",
+        "",
+        "hide:",
+        "- qux-1",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let watched = watched(&lines);
+    assert_eq!(
+        watched
+            .iter()
+            .map(|w| (w.line, w.versioned.to_string(), w.comment.as_deref()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                5,
+                "foo-1.0".to_owned(),
+                Some("Remove the bound on foo in \"A @a\"")
+            ),
+            (6, "bar-2.0.1".to_owned(), None),
+            (7, "baz-3".to_owned(), None),
+        ]
+    );
+
+    let latest: BTreeMap<Package, Version> = [("foo", "1.0.1"), ("bar", "2.0.1")]
+        .into_iter()
+        .map(|(p, v)| (Package::from(p), Version::try_from(v).unwrap()))
+        .collect();
+    let fired: Vec<(String, String)> = fired(&watched, &latest)
+        .into_iter()
+        .map(|(w, v)| (w.versioned.to_string(), v.to_string()))
+        .collect();
+    assert_eq!(fired, vec![("foo-1.0".to_owned(), "1.0.1".to_owned())]);
+}
@@ -0,0 +1,371 @@
+use crate::prelude::*;
+
+use crate::build_constraints::Maintainer;
+use crate::command::culprits::{self, Culprit};
+use crate::command::disabled;
+use crate::command::maintainer::{self, Index, Status};
+use crate::latest_version::latest_version;
+use crate::sections;
+use crate::types::{Package, Version};
+
+/// Writes static HTML pages for all packages, maintainers and culprits
+/// to `out`. Nothing is fetched over the network and the pages have no
+/// scripts, so the directory can be published as is.
+pub fn site(build_constraints: &Path, out: &Path, snapshot: Option<&Path>, no_hackage: bool) {
+    let snapshot = maintainer::snapshot_versions(snapshot);
+    let pages = pages(build_constraints, &snapshot, no_hackage);
+    for dir in ["package", "maintainer", "culprit"] {
+        fs::create_dir_all(out.join(dir)).unwrap();
+    }
+    let count = pages.len();
+    for (path, lines) in pages {
+        fs::write_lines(out.join(path), lines);
+    }
+    println!("Wrote {count} pages to {}", out.display());
+}
+
+type MaintainerSection<'a> = (&'a Maintainer, &'a [(Package, String)]);
+
+fn pages(
+    build_constraints: &Path,
+    snapshot: &BTreeMap<Package, Version>,
+    no_hackage: bool,
+) -> BTreeMap<String, Vec<String>> {
+    let lines = sections::read(build_constraints);
+    let maintainers = maintainer::maintainer_sections(&lines);
+    let index = Index::new(&lines);
+    let culprits = culprits::blocked(build_constraints);
+    let disabled = disabled::disabled_map(build_constraints);
+
+    // Every entry of every section, e.g. "Grandfathered dependencies"
+    let mut entries: BTreeMap<Package, Vec<(String, String)>> = BTreeMap::new();
+    for section in sections::sections(&lines) {
+        for entry in &section.entries {
+            entries
+                .entry(entry.package.clone())
+                .or_default()
+                .push((section.name.clone(), lines[entry.line].trim().to_owned()));
+        }
+    }
+    let hackage = if no_hackage {
+        BTreeMap::new()
+    } else {
+        latest_version(entries.keys())
+    };
+
+    let mut maintainers_of: BTreeMap<&Package, Vec<&Maintainer>> = BTreeMap::new();
+    let mut by_file: BTreeMap<String, Vec<MaintainerSection>> = BTreeMap::new();
+    for (m, packages) in &maintainers {
+        for (package, _) in packages {
+            maintainers_of.entry(package).or_default().push(m);
+        }
+        by_file
+            .entry(maintainer::file_name(m))
+            .or_default()
+            .push((m, packages));
+    }
+
+    let mut culprits_of: BTreeMap<&Package, Vec<(&Culprit, &'static str)>> = BTreeMap::new();
+    for (culprit, blocked) in &culprits {
+        for (kind, packages) in [
+            ("library", &blocked.libs),
+            ("test-suite", &blocked.tests),
+            ("benchmark", &blocked.benches),
+        ] {
+            for package in packages {
+                culprits_of
+                    .entry(package)
+                    .or_default()
+                    .push((culprit, kind));
+            }
+        }
+    }
+
+    let status = |package: &Package| -> Status {
+        let bound = maintainers
+            .iter()
+            .flat_map(|(_, e)| e)
+            .find(|(p, _)| p == package)
+            .map_or("", |(_, b)| b);
+        index.status(package, bound, &hackage, snapshot)
+    };
+
+    let mut pages = BTreeMap::new();
+
+    for (package, in_sections) in &entries {
+        let s = status(package);
+        let mut body = vec![format!("<h1>{}</h1>", escape(&package.0))];
+        body.push("<dl>".to_owned());
+        let mut item = |term: &str, value: String| {
+            body.push(format!("<dt>{term}</dt><dd>{value}</dd>"));
+        };
+        let maintained_by = maintainers_of.get(package).map_or(vec![], |ms| {
+            ms.iter().map(|m| maintainer_link("../", m)).collect()
+        });
+        item("Maintainers", or_none(maintained_by.join(", ")));
+        item("Disabled", or_none(escape(&s.disabled.join("; "))));
+        item("Tests disabled", or_none(escape(&s.tests.join("; "))));
+        item(
+            "Benchmarks disabled",
+            or_none(escape(&s.benches.join("; "))),
+        );
+        item("Listed in", or_none(s.lists.join(", ")));
+        item("Hackage", or_none(version(&s.hackage)));
+        item("Snapshot", or_none(version(&s.snapshot)));
+        body.push("</dl>".to_owned());
+
+        body.push("<h2>Entries</h2>".to_owned());
+        body.push("<ul>".to_owned());
+        for (section, line) in in_sections {
+            body.push(format!(
+                "<li>{}: <code>{}</code></li>",
+                escape(section),
+                escape(line)
+            ));
+        }
+        body.push("</ul>".to_owned());
+
+        if let Some(cs) = culprits_of.get(package) {
+            body.push("<h2>Blocked by</h2>".to_owned());
+            body.push("<ul>".to_owned());
+            for (culprit, kind) in cs {
+                body.push(format!(
+                    "<li>{} ({kind})</li>",
+                    culprit_link("../", culprit)
+                ));
+            }
+            body.push("</ul>".to_owned());
+        }
+
+        let blocking: Vec<&Culprit> = culprits
+            .keys()
+            .filter(|c| match c {
+                Culprit::Version(v) => v.package == *package,
+                Culprit::Disabled(p) => p == package,
+            })
+            .collect();
+        if !blocking.is_empty() {
+            body.push("<h2>Blocking</h2>".to_owned());
+            body.push("<ul>".to_owned());
+            for culprit in blocking {
+                body.push(format!("<li>{}</li>", culprit_link("../", culprit)));
+            }
+            body.push("</ul>".to_owned());
+        }
+
+        if let Some((children, _)) = disabled.get(package) {
+            if !children.is_empty() {
+                body.push("<h2>Disabled because of this package</h2>".to_owned());
+                body.push("<ul>".to_owned());
+                for child in children.iter().map(|c| &c.package).collect::<BTreeSet<_>>() {
+                    body.push(format!("<li>{}</li>", package_link("../", child, &entries)));
+                }
+                body.push("</ul>".to_owned());
+            }
+        }
+        pages.insert(
+            format!("package/{package}.html"),
+            page(&package.0, "../", body),
+        );
+    }
+
+    for (file, sections) in &by_file {
+        let title = sections
+            .iter()
+            .map(|(m, _)| m.header.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut body = vec![format!("<h1>{}</h1>", escape(&title))];
+        body.push(
+            "<table><tr><th>Package</th><th>Bound</th><th>Disabled</th><th>Tests</th><th>Benchmarks</th><th>Hackage</th><th>Snapshot</th></tr>"
+                .to_owned(),
+        );
+        for (package, bound) in sections.iter().flat_map(|(_, p)| p.iter()) {
+            let s = status(package);
+            body.push(format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                package_link("../", package, &entries),
+                escape(bound),
+                escape(&s.disabled.join("; ")),
+                escape(&s.tests.join("; ")),
+                escape(&s.benches.join("; ")),
+                version(&s.hackage),
+                version(&s.snapshot),
+            ));
+        }
+        body.push("</table>".to_owned());
+        pages.insert(format!("maintainer/{file}.html"), page(&title, "../", body));
+    }
+
+    for (culprit, blocked) in &culprits {
+        let mut body = vec![format!("<h1>{}</h1>", escape(&culprit.to_string()))];
+        for (kind, packages) in [
+            ("Libraries and executables", &blocked.libs),
+            ("Test suites", &blocked.tests),
+            ("Benchmarks", &blocked.benches),
+        ] {
+            if packages.is_empty() {
+                continue;
+            }
+            body.push(format!("<h2>{kind} ({})</h2>", packages.len()));
+            body.push("<ul>".to_owned());
+            for package in packages {
+                body.push(format!(
+                    "<li>{}</li>",
+                    package_link("../", package, &entries)
+                ));
+            }
+            body.push("</ul>".to_owned());
+        }
+        pages.insert(
+            format!("culprit/{}.html", culprit.slug()),
+            page(&culprit.to_string(), "../", body),
+        );
+    }
+
+    let mut body = vec!["<h1>Stackage build-constraints</h1>".to_owned()];
+    body.push(format!(
+        "<p>{} packages, {} maintainers, {} culprits</p>",
+        entries.len(),
+        by_file.len(),
+        culprits.len()
+    ));
+    body.push("<h2>Culprits</h2>".to_owned());
+    body.push(
+        "<table><tr><th>Culprit</th><th>Libraries</th><th>Test suites</th><th>Benchmarks</th></tr>"
+            .to_owned(),
+    );
+    let mut ranked: Vec<_> = culprits.iter().collect();
+    ranked.sort_by_key(|(c, b)| {
+        (
+            std::cmp::Reverse(b.libs.len() + b.tests.len() + b.benches.len()),
+            *c,
+        )
+    });
+    for (culprit, blocked) in ranked {
+        body.push(format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            culprit_link("", culprit),
+            blocked.libs.len(),
+            blocked.tests.len(),
+            blocked.benches.len()
+        ));
+    }
+    body.push("</table>".to_owned());
+    body.push("<h2>Maintainers</h2>".to_owned());
+    body.push("<ul>".to_owned());
+    for sections in by_file.values() {
+        let (m, _) = sections[0];
+        let count: usize = sections.iter().map(|(_, p)| p.len()).sum();
+        body.push(format!("<li>{} ({count})</li>", maintainer_link("", m)));
+    }
+    body.push("</ul>".to_owned());
+    body.push("<h2>Packages</h2>".to_owned());
+    body.push("<p>".to_owned());
+    for package in entries.keys() {
+        body.push(format!("{} ", package_link("", package, &entries)));
+    }
+    body.push("</p>".to_owned());
+    pages.insert(
+        "index.html".to_owned(),
+        page("Stackage build-constraints", "", body),
+    );
+
+    pages
+}
+
+fn page(title: &str, root: &str, body: Vec<String>) -> Vec<String> {
+    let mut out = vec![
+        "<!DOCTYPE html>".to_owned(),
+        "<html>".to_owned(),
+        "<head>".to_owned(),
+        "<meta charset=\"utf-8\">".to_owned(),
+        format!("<title>{}</title>", escape(title)),
+        "<style>body { font-family: sans-serif; max-width: 70em; margin: auto; } td, th { padding: 0.2em 0.5em; text-align: left; } tr:nth-child(even) { background: #f4f4f4; }</style>".to_owned(),
+        "</head>".to_owned(),
+        "<body>".to_owned(),
+        format!("<p><a href=\"{root}index.html\">Index</a></p>"),
+    ];
+    out.extend(body);
+    out.push("</body>".to_owned());
+    out.push("</html>".to_owned());
+    out
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn or_none(s: String) -> String {
+    if s.is_empty() {
+        "-".to_owned()
+    } else {
+        s
+    }
+}
+
+fn version(v: &Option<Version>) -> String {
+    v.as_ref().map_or(String::new(), |v| v.to_string())
+}
+
+/// Packages that are not mentioned in any section (e.g. culprits that
+/// are only dependencies) have no page and are not linked.
+fn package_link<A>(root: &str, package: &Package, pages: &BTreeMap<Package, A>) -> String {
+    if pages.contains_key(package) {
+        format!("<a href=\"{root}package/{package}.html\">{package}</a>")
+    } else {
+        package.to_string()
+    }
+}
+
+fn maintainer_link(root: &str, m: &Maintainer) -> String {
+    format!(
+        "<a href=\"{root}maintainer/{}.html\">{}</a>",
+        maintainer::file_name(m),
+        escape(&m.header)
+    )
+}
+
+fn culprit_link(root: &str, culprit: &Culprit) -> String {
+    format!(
+        "<a href=\"{root}culprit/{}.html\">{}</a>",
+        culprit.slug(),
+        escape(&culprit.to_string())
+    )
+}
+
+#[test]
+fn test_pages_links() {
+    let pages = pages(
+        &PathBuf::from("test/build-constraints.yaml"),
+        &BTreeMap::new(),
+        true,
+    );
+    assert!(pages.contains_key("index.html"));
+    assert!(pages.contains_key("package/aeson.html"));
+    assert!(pages.contains_key("maintainer/bergmark.html"));
+    for (path, lines) in &pages {
+        let dir = Path::new(path).parent().unwrap();
+        for line in lines {
+            for cap in regex!(r#"href="([^"]+)""#).captures_iter(line) {
+                let mut target: Vec<&str> = dir.iter().map(|c| c.to_str().unwrap()).collect();
+                for part in cap[1].split('/') {
+                    match part {
+                        ".." => {
+                            target.pop();
+                        }
+                        _ => target.push(part),
+                    }
+                }
+                let target = target.join("/");
+                assert!(
+                    pages.contains_key(&target),
+                    "{path} links to missing {target}"
+                );
+            }
+        }
+    }
+}
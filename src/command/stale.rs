@@ -0,0 +1,323 @@
+use crate::prelude::*;
+
+use std::ops::Range;
+
+use crate::build_constraints::{self, Maintenance};
+use crate::handle::{bounds_blocks, is_marker};
+use crate::regex::*;
+use crate::sections;
+use crate::snapshot::SnapshotYaml;
+use crate::types::Package;
+use crate::yaml;
+
+/// Top level keys that list packages, one per line.
+const LIST_SECTIONS: [&str; 7] = [
+    "skipped-tests",
+    "expected-test-failures",
+    "expected-haddock-failures",
+    "skipped-benchmarks",
+    "expected-benchmark-failures",
+    "skipped-builds",
+    "hide",
+];
+
+/// Top level keys that map packages to nested values.
+const MAP_SECTIONS: [&str; 2] = ["package-flags", "github-users"];
+
+/// Maps github organizations rather than packages to the handles of
+/// their members.
+const GITHUB_USERS: &str = "github-users";
+
+#[derive(Debug, PartialEq, Eq)]
+struct Item {
+    section: &'static str,
+    package: Package,
+    lines: Range<usize>,
+}
+
+pub fn stale(build_constraints: &Path, snapshot: &Path, remove: bool) {
+    let snapshot: SnapshotYaml = yaml::yaml_from_file(snapshot)
+        .unwrap_or_else(|e| panic!("Error reading snapshot {snapshot:?}: {e}"));
+    let mut known: BTreeSet<String> = snapshot
+        .packages
+        .into_iter()
+        .map(|p| p.hackage.0.package.0)
+        .collect();
+
+    let mut handles: BTreeSet<String> = BTreeSet::new();
+    let bc = build_constraints::parse(build_constraints);
+    for (maintenance, packages) in &bc.packages {
+        if let Maintenance::Maintainer(m) = maintenance {
+            known.extend(packages.iter().map(|p| p.package.0.clone()));
+            handles.extend(
+                m.handles
+                    .iter()
+                    .map(|h| h.trim_start_matches('@').to_owned()),
+            );
+        }
+    }
+    let known: BTreeSet<String> = known.into_iter().map(|p| p.to_lowercase()).collect();
+    let handles: BTreeSet<String> = handles.into_iter().map(|h| h.to_lowercase()).collect();
+
+    let mut lines = sections::read(build_constraints);
+    let stale: Vec<Item> = items(&lines)
+        .into_iter()
+        .filter(|i| is_stale(&lines, i, &known, &handles))
+        .collect();
+
+    for Item {
+        section,
+        package,
+        lines: range,
+    } in &stale
+    {
+        println!(
+            "{file}:{line}: {package} in {section}",
+            file = build_constraints.display(),
+            line = range.start + 1
+        );
+    }
+
+    if remove {
+        remove_items(&mut lines, stale);
+        fs::write_lines(build_constraints, lines);
+    }
+}
+
+/// An organization in `github-users` is live if any of its members is
+/// a current maintainer, other items if the package is in the snapshot
+/// or a maintainer section.
+fn is_stale(
+    lines: &[String],
+    item: &Item,
+    known: &BTreeSet<String>,
+    handles: &BTreeSet<String>,
+) -> bool {
+    if item.section == GITHUB_USERS {
+        !lines[item.lines.clone()]
+            .iter()
+            .filter_map(|l| Captures::new(regex!(r#"^ *- *([^ #]+)"#), l).ok())
+            .any(|cap| handles.contains(&cap.get::<String>(1).unwrap().to_lowercase()))
+    } else {
+        !known.contains(&item.package.0.to_lowercase())
+    }
+}
+
+/// Removes the items together with the comments directly above them,
+/// unless such a comment also heads the entries below the item. Keys
+/// left without entries become `key: []` or `key: {}`.
+fn remove_items(lines: &mut Vec<String>, mut items: Vec<Item>) {
+    let is_comment = |l: &str| l.trim_start().starts_with('#') && !is_marker(l);
+    items.sort_by_key(|i| i.lines.start);
+    for Item { lines: range, .. } in items.into_iter().rev() {
+        let indent = |l: &str| l.len() - l.trim_start().len();
+        let item_indent = indent(&lines[range.start]);
+        let mut start = range.start;
+        let next_is_entry = lines.get(range.end).is_some_and(|l| {
+            !l.trim().is_empty() && !l.trim_start().starts_with('#') && indent(l) >= item_indent
+        });
+        if !next_is_entry {
+            while start > 0
+                && is_comment(&lines[start - 1])
+                && indent(&lines[start - 1]) >= item_indent
+            {
+                start -= 1;
+            }
+        }
+        lines.drain(start..range.end);
+    }
+
+    for (sections, empty) in [(&LIST_SECTIONS[..], "[]"), (&MAP_SECTIONS[..], "{}")] {
+        for section in sections {
+            let Some(range) = sections::top_level(lines, section) else {
+                continue;
+            };
+            let has_entries = lines[range.clone()]
+                .iter()
+                .any(|l| !l.trim().is_empty() && !l.trim_start().starts_with('#'));
+            let header = &mut lines[range.start - 1];
+            if !has_entries && header.trim_end() == format!("{section}:") {
+                *header = format!("{section}: {empty}");
+            }
+        }
+    }
+}
+
+fn items(lines: &[String]) -> Vec<Item> {
+    let generated: Vec<Range<usize>> = bounds_blocks(lines).into_iter().map(|(_, r)| r).collect();
+    let mut items = vec![];
+    for section in LIST_SECTIONS {
+        for (i, package) in sections::list_items(lines, section) {
+            if !generated.iter().any(|r| r.contains(&i)) {
+                items.push(Item {
+                    section,
+                    package,
+                    lines: i..i + 1,
+                });
+            }
+        }
+    }
+    for section in MAP_SECTIONS {
+        let Some(range) = sections::top_level(lines, section) else {
+            continue;
+        };
+        let mut current: Option<(usize, Package, usize)> = None;
+        let mut last_nested = 0;
+        for i in range.clone() {
+            let line = &lines[i];
+            if line.trim().is_empty() {
+                continue;
+            }
+            let indent = line.len() - line.trim_start().len();
+            if let Some((_, _, key_indent)) = &current {
+                if indent > *key_indent {
+                    last_nested = i;
+                    continue;
+                }
+            }
+            if let Some((start, package, _)) = current.take() {
+                items.push(Item {
+                    section,
+                    package,
+                    lines: start..last_nested + 1,
+                });
+            }
+            if let Ok(cap) = Captures::new(regex!(r#"^( +)([^ #:-][^ :]*): *(?:#.*)?$"#), line) {
+                let package: Package = cap.get(2).unwrap();
+                current = Some((i, package, indent));
+                last_nested = i;
+            }
+        }
+        if let Some((start, package, _)) = current {
+            items.push(Item {
+                section,
+                package,
+                lines: start..last_nested + 1,
+            });
+        }
+    }
+    items
+}
+
+#[test]
+fn test_items() {
+    let lines: Vec<String> = [
+        "package-flags:",
+        "    foo:",
+        "        bar: true",
+        "",
+        "    # comment",
+        "    baz:",
+        "        qux: false",
+        "        quux: false",
+        "# end of package-flags",
+        "skipped-tests:",
+        "    - a # comment",
+        "    # Test bounds issues",
+        "    - b # tried b-1",
+        "    # End of Test bounds issues",
+        "hide:",
+        "- c",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let item = |section, package: &str, lines| Item {
+        section,
+        package: package.into(),
+        lines,
+    };
+    assert_eq!(
+        items(&lines),
+        vec![
+            item("skipped-tests", "a", 10..11),
+            item("hide", "c", 15..16),
+            item("package-flags", "foo", 1..3),
+            item("package-flags", "baz", 5..8),
+        ]
+    );
+}
+
+#[test]
+fn test_items_build_constraints() {
+    let lines = sections::read(&PathBuf::from("test/build-constraints.yaml"));
+    let items = items(&lines);
+    assert!(items
+        .iter()
+        .any(|i| i.section == "github-users" && i.package == Package::from("diagrams")));
+    assert!(items
+        .iter()
+        .any(|i| i.section == "hide" && i.package == Package::from("HTF")));
+    assert!(items
+        .iter()
+        .all(|i| !lines[i.lines.start].contains("# tried")));
+}
+
+#[test]
+fn test_remove_items() {
+    let mut lines: Vec<String> = [
+        "package-flags:",
+        "    # why foo",
+        "    foo:",
+        "        bar: true",
+        "",
+        "skipped-tests:",
+        "    # these are broken",
+        "    - a",
+        "    - b",
+        "    # why c",
+        "    - c",
+        "    # Test bounds issues",
+        "    - d # tried d-1",
+        "    # End of Test bounds issues",
+        "hide:",
+        "- e",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let stale: Vec<Item> = items(&lines)
+        .into_iter()
+        .filter(|i| ["foo", "a", "c", "e"].contains(&&*i.package.0))
+        .collect();
+    remove_items(&mut lines, stale);
+    assert_eq!(
+        lines,
+        vec![
+            "package-flags: {}",
+            "",
+            "skipped-tests:",
+            "    # these are broken",
+            "    - b",
+            "    # Test bounds issues",
+            "    - d # tried d-1",
+            "    # End of Test bounds issues",
+            "hide: []",
+        ]
+    );
+}
+
+#[test]
+fn test_is_stale() {
+    let lines: Vec<String> = [
+        "github-users:",
+        "    yesodweb:",
+        "        - snoyberg",
+        "    gone:",
+        "        - someone",
+        "skipped-tests:",
+        "    - yesod",
+        "    - old",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let known = BTreeSet::from(["yesod".to_owned()]);
+    let handles = BTreeSet::from(["snoyberg".to_owned()]);
+    let stale: Vec<String> = items(&lines)
+        .into_iter()
+        .filter(|i| is_stale(&lines, i, &known, &handles))
+        .map(|i| i.package.to_string())
+        .collect();
+    assert_eq!(stale, vec!["old", "gone"]);
+}
@@ -0,0 +1,374 @@
+use crate::prelude::*;
+
+use serde::Serialize;
+
+use crate::build_constraints::{Maintainer, SPECIAL_SECTIONS};
+use crate::command::disabled::parse_disabled_transitviely;
+use crate::git;
+use crate::handle::{bounds_blocks, Location};
+use crate::sections;
+
+#[derive(Debug, Clone, Copy, strum::EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Mode {
+    Text,
+    Csv,
+    Json,
+}
+
+pub fn stats(build_constraints: &Path, history: bool, range: Option<&str>, mode: Mode) {
+    if history {
+        let rows: Vec<Row> = git::commits(build_constraints, range)
+            .into_iter()
+            .map(|(commit, date)| {
+                let counts = counts(&git::show(&commit, build_constraints));
+                Row {
+                    commit,
+                    date,
+                    counts,
+                }
+            })
+            .collect();
+        match mode {
+            Mode::Text | Mode::Csv => {
+                println!("commit,date,{}", Counts::HEADER.join(","));
+                for Row {
+                    commit,
+                    date,
+                    counts,
+                } in rows
+                {
+                    println!(
+                        "{commit},{date},{}",
+                        counts.values().map(|v| v.to_string()).join(",")
+                    );
+                }
+            }
+            Mode::Json => println!("{}", serde_json::to_string_pretty(&rows).unwrap()),
+        }
+        return;
+    }
+
+    let lines = sections::read(build_constraints);
+    let counts = counts(&lines);
+    match mode {
+        Mode::Text => {
+            for (name, value) in Counts::HEADER.iter().zip(counts.values()) {
+                println!("{name}: {value}");
+            }
+            print_summary(&summary(&lines));
+        }
+        Mode::Csv => {
+            println!("{}", Counts::HEADER.join(","));
+            println!("{}", counts.values().map(|v| v.to_string()).join(","));
+        }
+        Mode::Json => {
+            let summary = Summary {
+                counts: Some(counts),
+                ..summary(&lines)
+            };
+            println!("{}", serde_json::to_string_pretty(&summary).unwrap())
+        }
+    }
+}
+
+/// Number of maintainer sections listed in the text output.
+const LARGEST: usize = 10;
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+struct Summary {
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    counts: Option<Counts>,
+    /// Distinct packages in all sections.
+    packages: usize,
+    special_sections: BTreeMap<String, usize>,
+    maintainer_sections: BTreeMap<String, usize>,
+    /// Maintainer sections where every package is disabled.
+    all_disabled: Vec<String>,
+    disabled: DisabledCounts,
+}
+
+/// Distinct disabled packages by cause. A package disabled for several
+/// reasons is counted once, under the first of removed, compile, bounds
+/// and transitive, since e.g. a bounds failure of a package that does not
+/// compile does not matter.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+struct DisabledCounts {
+    bounds: usize,
+    transitive: usize,
+    compile: usize,
+    removed: usize,
+}
+
+fn is_disabled(line: &str) -> bool {
+    regex!(r#"^ *- +[^ #]+ *< *0\b"#).is_match(line)
+}
+
+/// In order of precedence, see `DisabledCounts`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Cause {
+    Removed,
+    Compile,
+    Bounds,
+    Transitive,
+}
+
+fn summary(lines: &[String]) -> Summary {
+    let mut summary = Summary::default();
+    let sections = sections::sections(lines);
+    let mut packages: BTreeSet<&crate::types::Package> = BTreeSet::new();
+    // Packages with a `< 0` line in any section
+    let mut disabled: BTreeSet<&crate::types::Package> = BTreeSet::new();
+    let mut causes: BTreeMap<&crate::types::Package, Cause> = BTreeMap::new();
+    for section in &sections {
+        for e in &section.entries {
+            packages.insert(&e.package);
+            if !is_disabled(&lines[e.line]) {
+                continue;
+            }
+            disabled.insert(&e.package);
+            let cause = match &*section.name {
+                "Library and exe bounds failures" => {
+                    if parse_disabled_transitviely(lines[e.line].trim()).is_some() {
+                        Cause::Transitive
+                    } else {
+                        Cause::Bounds
+                    }
+                }
+                "Compilation failures" => Cause::Compile,
+                "Removed packages" => Cause::Removed,
+                _ => continue,
+            };
+            let c = causes.entry(&e.package).or_insert(cause);
+            *c = (*c).min(cause);
+        }
+    }
+    for cause in causes.values() {
+        *match cause {
+            Cause::Removed => &mut summary.disabled.removed,
+            Cause::Compile => &mut summary.disabled.compile,
+            Cause::Bounds => &mut summary.disabled.bounds,
+            Cause::Transitive => &mut summary.disabled.transitive,
+        } += 1;
+    }
+
+    for section in &sections {
+        let name = section.name.clone();
+        if SPECIAL_SECTIONS.contains(&&*name) {
+            *summary.special_sections.entry(name).or_default() += section.entries.len();
+        } else {
+            if !section.entries.is_empty()
+                && section
+                    .entries
+                    .iter()
+                    .all(|e| disabled.contains(&e.package))
+            {
+                summary.all_disabled.push(name.clone());
+            }
+            *summary.maintainer_sections.entry(name).or_default() += section.entries.len();
+        }
+    }
+    summary.packages = packages.len();
+    summary
+}
+
+fn print_summary(summary: &Summary) {
+    let Summary {
+        counts: _,
+        packages,
+        special_sections,
+        maintainer_sections,
+        all_disabled,
+        disabled,
+    } = summary;
+    println!("packages: {packages}");
+    println!(
+        "disabled: {} bounds, {} transitive, {} compile, {} removed",
+        disabled.bounds, disabled.transitive, disabled.compile, disabled.removed
+    );
+    println!("maintainer sections: {}", maintainer_sections.len());
+
+    println!("\nSpecial sections:");
+    for (name, count) in special_sections {
+        println!("    {name}: {count}");
+    }
+
+    println!("\nLargest maintainer sections:");
+    let mut largest: Vec<(&String, &usize)> = maintainer_sections.iter().collect();
+    largest.sort_by(|(n1, c1), (n2, c2)| c2.cmp(c1).then_with(|| n1.cmp(n2)));
+    for (name, count) in largest.into_iter().take(LARGEST) {
+        println!("    {name}: {count}");
+    }
+
+    if !all_disabled.is_empty() {
+        println!("\nMaintainer sections with all packages disabled:");
+        for name in all_disabled {
+            println!("    {name}");
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Row {
+    commit: String,
+    date: String,
+    #[serde(flatten)]
+    counts: Counts,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+struct Counts {
+    maintained: usize,
+    lib_bounds: usize,
+    test_bounds: usize,
+    bench_bounds: usize,
+    compilation_failures: usize,
+    grandfathered: usize,
+    maintainers_without_handle: usize,
+}
+
+impl Counts {
+    const HEADER: [&'static str; 7] = [
+        "maintained",
+        "lib_bounds",
+        "test_bounds",
+        "bench_bounds",
+        "compilation_failures",
+        "grandfathered",
+        "maintainers_without_handle",
+    ];
+
+    fn values(&self) -> [usize; 7] {
+        [
+            self.maintained,
+            self.lib_bounds,
+            self.test_bounds,
+            self.bench_bounds,
+            self.compilation_failures,
+            self.grandfathered,
+            self.maintainers_without_handle,
+        ]
+    }
+}
+
+/// Counts are taken from the lines rather than the parsed yaml so that
+/// old revisions that do not parse anymore can still be counted.
+fn counts(lines: &[String]) -> Counts {
+    let mut counts = Counts::default();
+    let mut maintained: BTreeSet<&crate::types::Package> = BTreeSet::new();
+    let sections = sections::sections(lines);
+    for section in &sections {
+        match &*section.name {
+            "Compilation failures" => counts.compilation_failures += section.entries.len(),
+            "Grandfathered dependencies" => counts.grandfathered += section.entries.len(),
+            name if SPECIAL_SECTIONS.contains(&name) => {}
+            name => {
+                maintained.extend(section.entries.iter().map(|e| &e.package));
+                if Maintainer::parse(name).handles.is_empty() {
+                    counts.maintainers_without_handle += 1;
+                }
+            }
+        }
+    }
+    counts.maintained = maintained.len();
+
+    for (loc, range) in bounds_blocks(lines) {
+        let entries = lines[range]
+            .iter()
+            .filter(|l| l.trim_start().starts_with("- "))
+            .count();
+        match loc {
+            Location::Lib => counts.lib_bounds += entries,
+            Location::Test => counts.test_bounds += entries,
+            Location::Bench => counts.bench_bounds += entries,
+        }
+    }
+    counts
+}
+
+#[test]
+fn test_counts() {
+    let lines: Vec<String> = [
+        "packages:",
+        "    \"A @a\":",
+        "        - foo",
+        "        - bar",
+        "    \"B\":",
+        "        - foo",
+        "    \"Grandfathered dependencies\":",
+        "        - baz",
+        "    \"Compilation failures\":",
+        "        - qux < 0",
+        "    \"Library and exe bounds failures\":",
+        "        - a < 0 # tried a-1",
+        "    # End of Library and exe bounds failures",
+        "# end of packages",
+        "skipped-tests:",
+        "    # Test bounds issues",
+        "    - b # tried b-1",
+        "    - c # tried c-1",
+        "    # End of Test bounds issues",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    assert_eq!(
+        counts(&lines),
+        Counts {
+            maintained: 2,
+            lib_bounds: 1,
+            test_bounds: 2,
+            bench_bounds: 0,
+            compilation_failures: 1,
+            grandfathered: 1,
+            maintainers_without_handle: 1,
+        }
+    );
+}
+
+#[test]
+fn test_summary() {
+    let lines: Vec<String> = [
+        "packages:",
+        "    \"A @a\":",
+        "        - foo",
+        "        - bar",
+        "    \"B @b\":",
+        "        - foo < 0",
+        "    \"Removed packages\":",
+        "        - gone < 0",
+        "    \"Compilation failures\":",
+        "        - bar < 0",
+        "        - b < 0",
+        "        - qux < 0",
+        "    \"Library and exe bounds failures\":",
+        "        - a < 0 # tried a-1, but its *library* does not support: base-5",
+        "        - a < 0 # tried a-1, but its *executable* does not support: text-3",
+        "        - b < 0 # tried b-1, but its *library* requires the disabled package: a",
+        "        - c < 0 # tried c-1, but its *library* requires the disabled package: a",
+        "    # End of Library and exe bounds failures",
+        "# end of packages",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let summary = summary(&lines);
+    assert_eq!(summary.packages, 7);
+    // bar is disabled in "Compilation failures" and foo in "B @b"
+    assert_eq!(summary.all_disabled, vec!["A @a", "B @b"]);
+    assert_eq!(summary.maintainer_sections["A @a"], 2);
+    assert_eq!(
+        summary.special_sections["Library and exe bounds failures"],
+        4
+    );
+    // a is counted once and b as a compilation failure
+    assert_eq!(
+        summary.disabled,
+        DisabledCounts {
+            bounds: 1,
+            transitive: 1,
+            compile: 3,
+            removed: 1,
+        }
+    );
+}
@@ -0,0 +1,130 @@
+use crate::prelude::*;
+
+use crate::build_constraints::{self, BCPackage};
+use crate::command::culprits::{parse_culprit, Culprit};
+use crate::handle::{handle, Location};
+use crate::regex::*;
+use crate::types::*;
+
+use itertools::Itertools;
+
+pub fn test_bounds(build_constraints: &Path) {
+    let mut tests: Vec<String> = vec![];
+    let mut benches: Vec<String> = vec![];
+    handle(build_constraints, false, |loc, lines| {
+        match loc {
+            Location::Lib => (),
+            Location::Test => tests.extend(lines),
+            Location::Bench => benches.extend(lines),
+        }
+        vec![]
+    });
+
+    let bc = build_constraints::parse(build_constraints);
+
+    println!("TESTS");
+    report(
+        "test suites",
+        &tests,
+        &[
+            ("skipped-tests", manual_entries(&bc.skipped_tests, &tests)),
+            (
+                "expected-test-failures",
+                manual_entries(&bc.expected_test_failures, &[]),
+            ),
+        ],
+    );
+
+    println!();
+    println!("BENCHMARKS");
+    report(
+        "benchmarks",
+        &benches,
+        &[
+            (
+                "skipped-benchmarks",
+                manual_entries(&bc.skipped_benchmarks, &benches),
+            ),
+            (
+                "expected-benchmark-failures",
+                manual_entries(&bc.expected_benchmark_failures, &[]),
+            ),
+        ],
+    );
+}
+
+fn report(component: &str, lines: &[String], sections: &[(&str, BTreeSet<Package>)]) {
+    let mut groups: BTreeMap<Culprit, BTreeSet<Package>> = BTreeMap::new();
+    for line in lines {
+        if let Some((package, culprit)) = parse_culprit(line) {
+            groups.entry(culprit).or_default().insert(package);
+        }
+    }
+
+    let groups = groups
+        .into_iter()
+        .sorted_by(|(c1, p1), (c2, p2)| p2.len().cmp(&p1.len()).then_with(|| c1.cmp(c2)));
+    for (culprit, packages) in groups {
+        let (name, cause) = match &culprit {
+            Culprit::Version(v) => (v.to_string(), "unsupported version"),
+            Culprit::Disabled(p) => (p.to_string(), "disabled package"),
+        };
+        println!(
+            "{name} blocks {count} {component} ({cause})",
+            count = packages.len()
+        );
+        for package in packages {
+            let also: Vec<&str> = sections
+                .iter()
+                .filter(|(_, entries)| entries.contains(&package))
+                .map(|(section, _)| *section)
+                .collect();
+            if also.is_empty() {
+                println!("    {package}");
+            } else {
+                println!("    {package} (also in {})", also.join(", "));
+            }
+        }
+    }
+}
+
+/// Packages listed in a section, not counting the entries that were
+/// generated into its bounds issues block.
+fn manual_entries(entries: &[BCPackage], block: &[String]) -> BTreeSet<Package> {
+    let mut counts: BTreeMap<&Package, isize> = BTreeMap::new();
+    for entry in entries {
+        *counts.entry(&entry.package).or_default() += 1;
+    }
+    for line in block {
+        if let Ok(cap) = Captures::new(regex!(r#"^ *- *([^ ]+)"#), line) {
+            let package: Package = cap.get(1).unwrap();
+            if let Some(count) = counts.get_mut(&package) {
+                *count -= 1;
+            }
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(package, _)| package.clone())
+        .collect()
+}
+
+#[test]
+fn test_manual_entries() {
+    let bc = build_constraints::parse(&PathBuf::from("test/build-constraints.yaml"));
+    let mut tests: Vec<String> = vec![];
+    handle(
+        &PathBuf::from("test/build-constraints.yaml"),
+        false,
+        |loc, lines| {
+            if let Location::Test = loc {
+                tests.extend(lines);
+            }
+            vec![]
+        },
+    );
+    let manual = manual_entries(&bc.skipped_tests, &tests);
+    assert!(manual.contains(&Package::from("symengine")));
+    assert!(!manual.contains(&Package::from("IPv6DB")));
+}
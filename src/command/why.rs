@@ -0,0 +1,167 @@
+use crate::prelude::*;
+
+use crate::bounds_failure::{BoundsFailure, Cause};
+use crate::command::maintainer::maintainer_sections;
+use crate::sections;
+use crate::types::Package;
+
+/// Prints the chains of disabled packages that keep `package` out of
+/// the snapshot, down to the packages that are disabled for some other
+/// reason.
+pub fn why(build_constraints: &Path, package: &str) {
+    let lines = sections::read(build_constraints);
+    let why = Why::new(&lines);
+    let package = Package::from(package);
+    if !why.reasons.contains_key(&package) {
+        println!("{package} is not disabled");
+        return;
+    }
+
+    let mut roots = BTreeMap::new();
+    for line in why.tree(&package, &mut vec![], &mut roots) {
+        println!("{line}");
+    }
+    println!("\nRoot causes:");
+    for (package, reason) in roots {
+        println!("    {} {}: {reason}", package, why.maintainer(&package));
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Reason {
+    /// "requires the disabled package"
+    Requires(Package),
+    /// Disabled for any other reason, with the section and comment.
+    Root(String),
+}
+
+struct Why {
+    reasons: BTreeMap<Package, Vec<Reason>>,
+    maintainers: BTreeMap<Package, String>,
+}
+
+impl Why {
+    fn new(lines: &[String]) -> Why {
+        let mut reasons: BTreeMap<Package, Vec<Reason>> = BTreeMap::new();
+        for section in sections::sections(lines) {
+            for entry in &section.entries {
+                let line = &lines[entry.line];
+                let Some((package, bound, comment)) = sections::split_entry(line) else {
+                    continue;
+                };
+                if !regex!(r#"^< *0\b"#).is_match(&bound) {
+                    continue;
+                }
+                let reason = match BoundsFailure::parse(line) {
+                    Some(BoundsFailure {
+                        cause: Cause::DisabledPackage(parent),
+                        ..
+                    }) => Reason::Requires(parent),
+                    _ if comment.is_empty() => Reason::Root(section.name.clone()),
+                    _ => Reason::Root(format!("{}: {comment}", section.name)),
+                };
+                let reasons = reasons.entry(package).or_default();
+                if !reasons.contains(&reason) {
+                    reasons.push(reason);
+                }
+            }
+        }
+
+        let mut maintainers: BTreeMap<Package, String> = BTreeMap::new();
+        for (m, entries) in maintainer_sections(lines) {
+            for (package, _) in entries {
+                maintainers
+                    .entry(package)
+                    .or_insert_with(|| m.header.clone());
+            }
+        }
+
+        Why {
+            reasons,
+            maintainers,
+        }
+    }
+
+    fn maintainer(&self, package: &Package) -> String {
+        match self.maintainers.get(package) {
+            Some(header) => format!("({header})"),
+            None => "(no maintainer)".to_owned(),
+        }
+    }
+
+    /// The lines of the tree below `package`, collecting the root causes
+    /// that are reached.
+    fn tree(
+        &self,
+        package: &Package,
+        path: &mut Vec<Package>,
+        roots: &mut BTreeMap<Package, String>,
+    ) -> Vec<String> {
+        let indent = "  ".repeat(path.len());
+        let mut out = vec![format!("{indent}{package} {}", self.maintainer(package))];
+        if path.contains(package) {
+            out[0].push_str(" (cycle)");
+            return out;
+        }
+
+        let Some(reasons) = self.reasons.get(package) else {
+            let reason = "not disabled in build-constraints".to_owned();
+            out.push(format!("{indent}  root cause: {reason}"));
+            roots.insert(package.clone(), reason);
+            return out;
+        };
+
+        path.push(package.clone());
+        for reason in reasons {
+            match reason {
+                Reason::Requires(parent) => out.extend(self.tree(parent, path, roots)),
+                Reason::Root(reason) => {
+                    out.push(format!("{indent}  root cause: {reason}"));
+                    roots.insert(package.clone(), reason.clone());
+                }
+            }
+        }
+        path.pop();
+        out
+    }
+}
+
+#[test]
+fn test_why() {
+    let lines: Vec<String> = [
+        "packages:",
+        "    \"A @a\":",
+        "        - a",
+        "        - c < 0 # Uses an unreleased API",
+        "    \"B @b\":",
+        "        - b",
+        "    \"Compilation failures\":",
+        "        - d < 0",
+        "    \"Library and exe bounds failures\":",
+        "        - a < 0 # tried a-1, but its *library* requires the disabled package: b",
+        "        - a < 0 # tried a-1, but its *test-suite* requires the disabled package: c",
+        "        - b < 0 # tried b-1, but its *library* requires the disabled package: d",
+        "    # End of Library and exe bounds failures",
+        "# end of packages",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let why = Why::new(&lines);
+    let mut roots = BTreeMap::new();
+    assert_eq!(
+        why.tree(&Package::from("a"), &mut vec![], &mut roots),
+        vec![
+            "a (A @a)",
+            "  b (B @b)",
+            "    d (no maintainer)",
+            "      root cause: Compilation failures",
+            "  c (A @a)",
+            "    root cause: A @a: Uses an unreleased API",
+        ]
+    );
+    assert_eq!(
+        roots.into_keys().collect::<Vec<_>>(),
+        vec![Package::from("c"), Package::from("d")]
+    );
+}
@@ -0,0 +1,169 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Contents of `path` (relative to the current directory) at `rev`.
+pub fn show(rev: &str, path: &Path) -> Vec<String> {
+    let res = call_git(&["show", &format!("{rev}:./{}", path.display())]);
+    if !res.status.success() {
+        eprintln!(
+            "ERROR: Could not read {path:?} at {rev}: {}",
+            String::from_utf8_lossy(&res.stderr).trim()
+        );
+        std::process::exit(1);
+    }
+    lines(res.stdout)
+}
+
+/// `(hash, date)` of the commits touching `path` in `range` (all of
+/// history if `None`), oldest first.
+pub fn commits(path: &Path, range: Option<&str>) -> Vec<(String, String)> {
+    let path = path.display().to_string();
+    let mut args = vec!["log", "--reverse", "--date=short", "--format=%H%x09%ad"];
+    args.extend(range);
+    args.extend(["--", &path]);
+    let res = call_git(&args);
+    if !res.status.success() {
+        eprintln!(
+            "ERROR: git log failed: {}",
+            String::from_utf8_lossy(&res.stderr).trim()
+        );
+        std::process::exit(1);
+    }
+    lines(res.stdout)
+        .into_iter()
+        .filter_map(|l| {
+            let (hash, date) = l.split_once('\t')?;
+            Some((hash.to_owned(), date.to_owned()))
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Commit {
+    pub hash: String,
+    pub date: String,
+    pub author: String,
+    pub subject: String,
+    pub changes: Vec<Change>,
+}
+
+/// A changed line with its 0-based index in the file after (for
+/// `Added`) or before (for `Removed`) the commit.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Change {
+    Added(usize, String),
+    Removed(usize, String),
+}
+
+/// Commits touching `path` where a changed line matches the extended
+/// regex `pattern`, oldest first.
+pub fn log(path: &Path, pattern: &str) -> Vec<Commit> {
+    let res = call_git(&[
+        "log",
+        "--reverse",
+        "--date=short",
+        "--format=%x00%H%x09%ad%x09%an%x09%s",
+        "--unified=0",
+        "--extended-regexp",
+        &format!("-G{pattern}"),
+        "--",
+        &path.display().to_string(),
+    ]);
+    if !res.status.success() {
+        eprintln!(
+            "ERROR: git log failed: {}",
+            String::from_utf8_lossy(&res.stderr).trim()
+        );
+        std::process::exit(1);
+    }
+    parse_log(&lines(res.stdout))
+}
+
+fn parse_log(lines: &[String]) -> Vec<Commit> {
+    let mut commits: Vec<Commit> = vec![];
+    let (mut old, mut new) = (0, 0);
+    for line in lines {
+        if let Some(header) = line.strip_prefix('\0') {
+            let mut fields = header.splitn(4, '\t').map(|s| s.to_owned());
+            let mut field = || fields.next().unwrap_or_default();
+            commits.push(Commit {
+                hash: field(),
+                date: field(),
+                author: field(),
+                subject: field(),
+                changes: vec![],
+            });
+        } else if let Some(hunk) = line.strip_prefix("@@ ") {
+            // @@ -old[,len] +new[,len] @@
+            let start = |prefix: char| -> usize {
+                let range = hunk
+                    .split(' ')
+                    .find_map(|s| s.strip_prefix(prefix))
+                    .unwrap();
+                let start: usize = range.split(',').next().unwrap().parse().unwrap();
+                start.saturating_sub(1)
+            };
+            old = start('-');
+            new = start('+');
+        } else if line.starts_with("+++ ") || line.starts_with("--- ") {
+            continue;
+        } else if let Some(commit) = commits.last_mut() {
+            if let Some(added) = line.strip_prefix('+') {
+                commit.changes.push(Change::Added(new, added.to_owned()));
+                new += 1;
+            } else if let Some(removed) = line.strip_prefix('-') {
+                commit
+                    .changes
+                    .push(Change::Removed(old, removed.to_owned()));
+                old += 1;
+            }
+        }
+    }
+    commits
+}
+
+#[test]
+fn test_parse_log() {
+    let log = "\0abc\t2023-01-02\tA Person\tDisable foo
+diff --git a/build-constraints.yaml b/build-constraints.yaml
+index 1..2 100644
+--- a/build-constraints.yaml
++++ b/build-constraints.yaml
+@@ -10 +10,2 @@ packages:
+-        - foo
++        - foo < 0 # tried foo-1
++        - bar
+@@ -20,0 +22 @@ skipped-tests:
++    - foo";
+    let lines: Vec<String> = log.lines().map(|l| l.to_owned()).collect();
+    assert_eq!(
+        parse_log(&lines),
+        vec![Commit {
+            hash: "abc".to_owned(),
+            date: "2023-01-02".to_owned(),
+            author: "A Person".to_owned(),
+            subject: "Disable foo".to_owned(),
+            changes: vec![
+                Change::Removed(9, "        - foo".to_owned()),
+                Change::Added(9, "        - foo < 0 # tried foo-1".to_owned()),
+                Change::Added(10, "        - bar".to_owned()),
+                Change::Added(21, "    - foo".to_owned()),
+            ],
+        }]
+    );
+}
+
+fn call_git(args: &[&str]) -> Output {
+    Command::new("git")
+        .args(args)
+        .output()
+        .expect("Could not find git in PATH")
+}
+
+fn lines(x: Vec<u8>) -> Vec<String> {
+    String::from_utf8(x)
+        .unwrap()
+        .lines()
+        .map(|s| s.to_owned())
+        .collect()
+}
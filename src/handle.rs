@@ -1,11 +1,20 @@
 use crate::prelude::*;
 
-use std::fs::File;
-use std::io::{LineWriter, Write};
+use std::ops::Range;
 
+use crate::bounds_failure::BoundsFailure;
 use crate::regex::*;
 use crate::types::*;
 
+const LIB_START: &str = r#"    "Library and exe bounds failures":"#;
+const LIB_END: &str = r#"    # End of Library and exe bounds failures"#;
+const TEST_START: &str = r#"    # Test bounds issues"#;
+const TEST_END: &str = r#"    # End of Test bounds issues"#;
+const BENCH_START: &str = r#"    # Benchmark bounds issues"#;
+const BENCH_END: &str = r#"    # End of Benchmark bounds issues"#;
+pub(crate) const UPPER_BOUNDS_START: &str = r#"        # Generated upper bounds"#;
+pub(crate) const UPPER_BOUNDS_END: &str = r#"        # End of generated upper bounds"#;
+
 pub struct DisabledPackage {
     pub package: Package,
 }
@@ -33,14 +42,14 @@ where
 
         match state {
             State::LookingForLibBounds => {
-                if line == r#"    "Library and exe bounds failures":"# {
+                if line == LIB_START {
                     state = State::ProcessingLibBounds;
                 }
                 new_lines.push(line);
             }
             State::ProcessingLibBounds => {
-                if line == r#"    # End of Library and exe bounds failures"# {
-                    new_lines.extend(f(Location::Lib, buf).into_iter());
+                if line == LIB_END {
+                    new_lines.extend(f(Location::Lib, buf));
                     buf = vec![];
                     new_lines.push(line);
                     state = State::LookingForTestBounds;
@@ -52,14 +61,14 @@ where
                 }
             }
             State::LookingForTestBounds => {
-                if line == r#"    # Test bounds issues"# {
+                if line == TEST_START {
                     state = State::ProcessingTestBounds;
                 }
                 new_lines.push(line);
             }
             State::ProcessingTestBounds => {
-                if line == r#"    # End of Test bounds issues"# {
-                    new_lines.extend(f(Location::Test, buf).into_iter());
+                if line == TEST_END {
+                    new_lines.extend(f(Location::Test, buf));
                     buf = vec![];
                     new_lines.push(line);
                     state = State::LookingForBenchBounds;
@@ -68,14 +77,14 @@ where
                 }
             }
             State::LookingForBenchBounds => {
-                if line == r#"    # Benchmark bounds issues"# {
+                if line == BENCH_START {
                     state = State::ProcessingBenchBounds;
                 }
                 new_lines.push(line);
             }
             State::ProcessingBenchBounds => {
-                if line == r#"    # End of Benchmark bounds issues"# {
-                    new_lines.extend(f(Location::Bench, buf).into_iter());
+                if line == BENCH_END {
+                    new_lines.extend(f(Location::Bench, buf));
                     buf = vec![];
                     new_lines.push(line);
                     state = State::Done;
@@ -90,16 +99,72 @@ where
     }
 
     if write {
-        let file = File::create(build_constraints).unwrap();
-        let mut file = LineWriter::new(file);
+        fs::write_lines(build_constraints, new_lines);
+    }
+
+    (versioned_packages, disabled_packages)
+}
 
-        for line in new_lines {
-            file.write_all((line + "\n").as_bytes()).unwrap();
+/// The line ranges of the bounds blocks, excluding the start and end
+/// markers.
+pub(crate) fn bounds_blocks(lines: &[String]) -> Vec<(Location, Range<usize>)> {
+    let find = |marker: &str, from: usize| {
+        lines
+            .iter()
+            .skip(from)
+            .position(|l| l == marker)
+            .map(|i| i + from)
+    };
+    let mut blocks = vec![];
+    let mut from = 0;
+    for (loc, start, end) in [
+        (Location::Lib, LIB_START, LIB_END),
+        (Location::Test, TEST_START, TEST_END),
+        (Location::Bench, BENCH_START, BENCH_END),
+    ] {
+        if let Some(s) = find(start, from) {
+            if let Some(e) = find(end, s) {
+                blocks.push((loc, s + 1..e));
+                from = e;
+            }
         }
-        file.flush().unwrap();
     }
+    blocks
+}
 
-    (versioned_packages, disabled_packages)
+/// Whether the line starts or ends a generated block.
+pub(crate) fn is_marker(line: &str) -> bool {
+    [
+        LIB_START,
+        LIB_END,
+        TEST_START,
+        TEST_END,
+        BENCH_START,
+        BENCH_END,
+        UPPER_BOUNDS_START,
+        UPPER_BOUNDS_END,
+    ]
+    .contains(&line)
+}
+
+/// The lines of the upper bounds generated by `add --strategy`,
+/// excluding the start and end markers.
+pub(crate) fn upper_bounds_block(lines: &[String]) -> Option<Range<usize>> {
+    let start = lines.iter().position(|l| l == UPPER_BOUNDS_START)?;
+    let end = lines[start..].iter().position(|l| l == UPPER_BOUNDS_END)? + start;
+    Some(start + 1..end)
+}
+
+#[test]
+fn test_bounds_blocks() {
+    let lines = fs::read_lines("test/build-constraints.yaml")
+        .map(|l| l.unwrap())
+        .collect::<Vec<_>>();
+    let blocks = bounds_blocks(&lines);
+    assert_eq!(blocks.len(), 3);
+    let (_, lib) = &blocks[0];
+    assert!(lines[lib.start].contains("BiobaseHTTP"));
+    assert!(lines[lib.end - 1].contains("zydiskell"));
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -113,7 +178,7 @@ enum State {
     Done,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Location {
     Lib,
     Test,
@@ -135,7 +200,9 @@ fn parse_versioned_package_yaml(s: &str) -> Option<VersionedPackage> {
 }
 
 fn parse_disabled_package(s: &str) -> Option<DisabledPackage> {
-    if !regex!(r#"- *([^ ]+) < *0 *# tried"#).is_match(s) {
+    // Generated lines in a wording the parser does not know are still
+    // not manual disables
+    if BoundsFailure::parse(s).is_none() && !regex!(r#"- *([^ ]+) < *0 *# tried"#).is_match(s) {
         Captures::new(regex!(r#"- *([^ ]+) < *0 *# *\d*[^\d ]"#), s)
             .ok()
             .map(|cap| DisabledPackage {
@@ -145,3 +212,22 @@ fn parse_disabled_package(s: &str) -> Option<DisabledPackage> {
         None
     }
 }
+
+#[test]
+fn test_parse_disabled_package() {
+    let package = |s| parse_disabled_package(s).map(|d| d.package.to_string());
+    assert_eq!(
+        package("        - foo < 0 # 1.2.3 compile fail"),
+        Some("foo".to_owned())
+    );
+    assert_eq!(
+        package(
+            "        - foo < 0 # tried foo-1, but its *library* requires the disabled package: bar"
+        ),
+        None
+    );
+    assert_eq!(
+        package("        - foo < 0 # tried foo-1, but its *library* is broken in a new way"),
+        None
+    );
+}
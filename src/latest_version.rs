@@ -18,44 +18,63 @@ pub fn latest_version_for(package: &Package) -> Option<Version> {
     latest_version([package].into_iter()).get(package).cloned()
 }
 
-fn all_versions<'a>(
-    packages: impl Iterator<Item = &'a Package>,
-) -> BTreeMap<Package, BTreeSet<Version>> {
-    let tilde = home::home_dir().unwrap_or_else(|| panic!("Could not find $HOME"));
+/// The contents of the latest revision of the .cabal file for each
+/// package version that is present in pantry.
+pub fn cabal_files<'a>(
+    packages: impl Iterator<Item = &'a VersionedPackage>,
+) -> BTreeMap<VersionedPackage, String> {
+    let conn = pantry();
     let mut res = BTreeMap::new();
-    let conn = Connection::open(format!(
+    for VersionedPackage { package, version } in packages {
+        let mut stmt = conn.prepare(
+            &format!("select blob.contents from hackage_cabal as h, version, blob where h.name=(select id from package_name where name = '{package}') and h.version=version.id and version.version='{version}' and h.cabal=blob.id order by h.revision desc limit 1")
+        ).unwrap();
+
+        let contents: Option<Vec<u8>> = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .next()
+            .map(|c| c.unwrap());
+
+        if let Some(contents) = contents {
+            res.insert(
+                VersionedPackage {
+                    package: package.clone(),
+                    version: version.clone(),
+                },
+                String::from_utf8_lossy(&contents).into_owned(),
+            );
+        }
+    }
+    res
+}
+
+fn pantry() -> Connection {
+    let tilde = home::home_dir().unwrap_or_else(|| panic!("Could not find $HOME"));
+    Connection::open(format!(
         "{tilde}/.stack/pantry/pantry.sqlite3",
         tilde = tilde.display()
     ))
-    .unwrap();
+    .unwrap()
+}
+
+pub(crate) fn all_versions<'a>(
+    packages: impl Iterator<Item = &'a Package>,
+) -> BTreeMap<Package, BTreeSet<Version>> {
+    let mut res = BTreeMap::new();
+    let conn = pantry();
     for package in packages {
         let mut stmt = conn.prepare(
-            &format!("select version.version, revision from hackage_cabal as h, version where h.name=(select id from package_name where name = '{package}') and h.version=version.id")
+            &format!("select version.version from hackage_cabal as h, version where h.name=(select id from package_name where name = '{package}') and h.version=version.id")
         ).unwrap();
 
-        let version_revisions = stmt
-            .query_map([], |row| {
-                Ok(VersionRevision {
-                    version: row.get(0).unwrap(),
-                    revision: row.get(1).unwrap(),
-                })
-            })
-            .unwrap();
-
-        for v in version_revisions {
-            let VersionRevision {
-                version,
-                revision: _,
-            } = v.unwrap();
+        let versions = stmt.query_map([], |row| row.get::<_, String>(0)).unwrap();
+
+        for version in versions {
+            let version = version.unwrap();
             let e = res.entry(package.clone()).or_insert_with(BTreeSet::new);
             e.insert((&*version).try_into().unwrap());
         }
     }
     res
 }
-
-#[derive(Debug)]
-pub struct VersionRevision {
-    pub version: String,
-    pub revision: usize,
-}
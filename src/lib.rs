@@ -1,11 +1,15 @@
+mod bounds_failure;
 mod build_constraints;
+mod cabal;
 pub mod command;
 mod curator;
+mod git;
 mod handle;
 mod ignores;
 mod latest_version;
 mod prelude;
 mod regex;
+mod sections;
 mod snapshot;
 mod types;
 mod util;
@@ -11,15 +11,48 @@ use commenter::command;
 )]
 enum Opt {
     /// Reads `curator` bounds failures from from stdin and disabled packages accordingly.
+    ///
+    /// Pass `--tag` to append the date, and optionally the `--target`
+    /// snapshot, to each generated comment.
+    ///
+    /// Pass `--strategy` to add an upper bound to "Stackage upper bounds"
+    /// instead of disabling the dependents of a dependency with at least
+    /// `--threshold` dependents, or that is in `--allow`. Dependencies
+    /// in `--deny` never get an upper bound.
+    ///
+    /// Pass `--issues-dir <dir>` to write a stackage issue body for each
+    /// culprit of the failures that are not in build-constraints yet, see
+    /// `issues`.
     Add {
         #[structopt(short, long, default_value = "build-constraints.yaml")]
         build_constraints: PathBuf,
+        #[structopt(long)]
+        tag: bool,
+        #[structopt(long, requires = "tag")]
+        target: Option<String>,
+        #[structopt(long)]
+        strategy: bool,
+        #[structopt(long, requires = "strategy", default_value = "10")]
+        threshold: usize,
+        #[structopt(long, requires = "strategy")]
+        allow: Vec<String>,
+        #[structopt(long, requires = "strategy")]
+        deny: Vec<String>,
+        #[structopt(long)]
+        issues_dir: Option<PathBuf>,
     },
     /// Like `add` but runs curator internally, looping until there
     /// are no more bounds failures.
     ///
     /// Pass `--clear` to remove all
     /// generated bounds (updating anything that is out of date).
+    ///
+    /// Pass `--tag` to append the target and date to each generated
+    /// comment.
+    ///
+    /// `--strategy`, `--threshold`, `--allow` and `--deny` work as for
+    /// `add`. Since `--clear` also removes the generated upper bounds,
+    /// pass the same strategy as before to get them back.
     AddLoop {
         #[structopt(short, long, default_value = "build-constraints.yaml")]
         build_constraints: PathBuf,
@@ -27,6 +60,32 @@ enum Opt {
         clear: bool,
         #[structopt(long)]
         target: Option<String>,
+        #[structopt(long)]
+        tag: bool,
+        #[structopt(long)]
+        strategy: bool,
+        #[structopt(long, requires = "strategy", default_value = "10")]
+        threshold: usize,
+        #[structopt(long, requires = "strategy")]
+        allow: Vec<String>,
+        #[structopt(long, requires = "strategy")]
+        deny: Vec<String>,
+    },
+    /// Lists generated bounds that were tagged by `add --tag` more than
+    /// `--days` days ago, oldest first.
+    Age {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
+        #[structopt(long, default_value = "30")]
+        days: i64,
+    },
+    /// Checks the manual bounds in maintainer sections and "Stackage
+    /// upper bounds" against the versions on Hackage (from pantry),
+    /// reporting bounds that exclude newer releases and bounds that no
+    /// longer exclude anything, with the issues they reference.
+    AuditBounds {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
     },
     /// Takes the diff of two snapshots and produces packages +
     /// maintainers of any removed packages, to be able to ping all
@@ -37,11 +96,38 @@ enum Opt {
         older: PathBuf,
         newer: PathBuf,
     },
+    /// Summarizes the changes between two versions of build-constraints:
+    /// section changes, bounds, disabled and re-enabled packages and
+    /// skipped tests.
+    ///
+    /// `old` and `new` are paths, or `git:REV` to read the
+    /// build-constraints file at that revision of the current git repo.
+    BcDiff {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
+        old: String,
+        new: String,
+        #[structopt(long, default_value = "text")]
+        mode: crate::command::bc_diff::Mode,
+    },
+    /// Prints the git history of all lines mentioning a package, with
+    /// the section each line was added to or removed from.
+    Blame {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
+        package: String,
+    },
     /// Removes all bounds that were generated by `add` from build-constraints
     Clear {
         #[structopt(short, long, default_value = "build-constraints.yaml")]
         build_constraints: PathBuf,
     },
+    /// Ranks the dependencies that cause bounds failures by how many
+    /// packages they keep out of the snapshot.
+    Culprits {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
+    },
     /// Produces a diff between two snapshots, showing added, removed,
     /// and up/down-graded packages.
     DiffSnapshot {
@@ -56,23 +142,129 @@ enum Opt {
     Disabled {
         #[structopt(short, long, default_value = "build-constraints.yaml")]
         build_constraints: PathBuf,
+        /// Print the tree of packages disabled because of this package
+        #[structopt(long)]
+        tree: Option<String>,
+        /// Export the whole graph of disabled packages (dot or json)
+        #[structopt(long, conflicts_with = "tree")]
+        graph: Option<crate::command::disabled::GraphFormat>,
+    },
+    /// Formats build-constraints: fixes indentation, removes stray blank
+    /// lines and sorts maintainer sections and bounds blocks.
+    ///
+    /// Pass `--check` to exit with an error instead if the file is not
+    /// formatted.
+    Fmt {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
+        #[structopt(long)]
+        check: bool,
     },
     /// Prints packages that are mentioned in comments but not
     /// elsewhere in a format that can be pasted into Grandfathered
     /// Dependencies.
+    ///
+    /// Pass `--write` to add them to build-constraints directly. Pass
+    /// `--prune --snapshot <file>` to instead list grandfathered
+    /// packages that are no longer needed.
     Grandfather {
         #[structopt(short, long, default_value = "build-constraints.yaml")]
         build_constraints: PathBuf,
+        #[structopt(long)]
+        write: bool,
+        #[structopt(long, requires = "snapshot")]
+        prune: bool,
+        /// Snapshot yaml file, used by `--prune` to find dependencies
+        #[structopt(long)]
+        snapshot: Option<PathBuf>,
+    },
+    /// Writes a markdown stackage issue body listing the affected
+    /// packages, components, bounds and maintainers for each package
+    /// causing generated bounds failures to `--out-dir`.
+    Issues {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
+        #[structopt(long)]
+        out_dir: PathBuf,
+    },
+    /// Lists the issues and pull requests referenced in comments with
+    /// the entries citing them, entries citing issues marked `/closed`
+    /// (candidates for re-enabling) and entries in "Stackage upper
+    /// bounds" without a reference. A reference in a comment on its own
+    /// line applies to the entries below it up to the next blank line or
+    /// header.
+    IssuesIndex {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
+        #[structopt(long, default_value = "text")]
+        mode: crate::command::issues_index::Mode,
+    },
+    /// Checks build-constraints for violations of curator conventions,
+    /// exiting with an error if any errors are found.
+    ///
+    /// Pass `--fix` to fix the violations that can be fixed automatically.
+    /// Pass `--deny-warnings` to also exit with an error on warnings, as
+    /// in a pre-commit hook: `commenter lint --deny-warnings`.
+    Lint {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
+        #[structopt(long)]
+        fix: bool,
+        #[structopt(long)]
+        deny_warnings: bool,
+    },
+    /// Lists the packages of a maintainer (given as `@handle`, email or
+    /// section name) with their bounds, why they are disabled, disabled
+    /// tests and benchmarks, and latest Hackage and snapshot versions.
+    ///
+    /// Pass `--all --out-dir <dir>` to write a markdown digest for every
+    /// maintainer instead.
+    Maintainer {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
+        #[structopt(required_unless_present = "all")]
+        query: Option<String>,
+        #[structopt(long, requires = "out_dir", conflicts_with = "query")]
+        all: bool,
+        #[structopt(long)]
+        out_dir: Option<PathBuf>,
+        /// Snapshot to take versions from
+        #[structopt(long)]
+        snapshot: Option<PathBuf>,
+        /// Don't look up the latest versions in pantry
+        #[structopt(long)]
+        no_hackage: bool,
     },
-    /// Prints maintainer sections with missing github handles
+    /// Prints maintainer sections with missing github handles or
+    /// malformed headers, and people that have several sections.
+    ///
+    /// Pass `--fix` to merge the sections of the same person.
     Maintainers {
         #[structopt(short, long, default_value = "build-constraints.yaml")]
         build_constraints: PathBuf,
+        #[structopt(long)]
+        fix: bool,
+    },
+    /// Moves packages to another section, e.g. a maintainer section or
+    /// "Abandoned packages", keeping their bounds and comments. Entries in
+    /// the bounds sections (e.g. "Stackage upper bounds") are not moved.
+    Move {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
+        #[structopt(long)]
+        to: String,
+        #[structopt(required = true)]
+        packages: Vec<String>,
     },
     /// Prints packages that are part of multiple maintainer sections
+    ///
+    /// Pass `--resolve keep-first` or `--resolve keep-last` to only keep
+    /// each package in the first or last of its sections in the file.
     Multiple {
         #[structopt(short, long, default_value = "build-constraints.yaml")]
         build_constraints: PathBuf,
+        #[structopt(long)]
+        resolve: Option<crate::command::multiple::Resolve>,
     },
     /// Finds mentioned package versions that are out of date.
     Outdated {
@@ -94,37 +286,205 @@ enum Opt {
         no_search_snapshots: bool,
         package: String,
     },
+    /// Checks the `tell-me-when-its-released` entries against the latest
+    /// versions on Hackage (from pantry), printing the comment of each
+    /// package that has a newer release and exiting with an error if
+    /// there are any.
+    Released {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
+    },
+    /// Generates a static HTML site with pages for every package,
+    /// maintainer and culprit.
+    Site {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
+        #[structopt(long)]
+        out: PathBuf,
+        /// Snapshot to take versions from
+        #[structopt(long)]
+        snapshot: Option<PathBuf>,
+        /// Don't look up the latest versions in pantry
+        #[structopt(long)]
+        no_hackage: bool,
+    },
+    /// Finds entries in sections such as skipped-tests, package-flags
+    /// and hide for packages that are neither in the snapshot nor in a
+    /// maintainer section, and github-users organizations none of whose
+    /// members is a maintainer.
+    Stale {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
+        #[structopt(long)]
+        snapshot: PathBuf,
+        /// Remove the stale entries from build-constraints
+        #[structopt(long)]
+        remove: bool,
+    },
+    /// Prints counts of maintained packages, generated bounds, disabled
+    /// packages by cause and per section, and the largest maintainer
+    /// sections.
+    ///
+    /// Pass `--history` to output the counts for every commit that
+    /// changed build-constraints (optionally limited to `--range`, e.g.
+    /// `v1..HEAD`) as CSV or JSON.
+    Stats {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
+        #[structopt(long)]
+        history: bool,
+        #[structopt(long, requires = "history")]
+        range: Option<String>,
+        #[structopt(long, default_value = "text")]
+        mode: crate::command::stats::Mode,
+    },
+    /// Groups test suites and benchmarks disabled by bounds issues by
+    /// the dependency blocking them.
+    TestBounds {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
+    },
+    /// Follows "requires the disabled package" chains from a disabled
+    /// package down to the packages that are disabled for other reasons,
+    /// printing the maintainer of each package on the way.
+    Why {
+        #[structopt(short, long, default_value = "build-constraints.yaml")]
+        build_constraints: PathBuf,
+        package: String,
+    },
 }
 
 fn main() {
     let opt = Opt::parse();
     match opt {
-        Opt::Add { build_constraints } => command::add::add(&build_constraints),
+        Opt::Add {
+            build_constraints,
+            tag,
+            target,
+            strategy,
+            threshold,
+            allow,
+            deny,
+            issues_dir,
+        } => command::add::add(
+            &build_constraints,
+            tag,
+            target,
+            strategy.then_some(command::add::Policy {
+                threshold,
+                allow,
+                deny,
+            }),
+            issues_dir.as_deref(),
+        ),
         Opt::AddLoop {
             build_constraints,
             clear,
             target,
-        } => command::add_loop::add_loop(&build_constraints, clear, target),
+            tag,
+            strategy,
+            threshold,
+            allow,
+            deny,
+        } => command::add_loop::add_loop(
+            &build_constraints,
+            clear,
+            target,
+            tag,
+            strategy.then_some(command::add::Policy {
+                threshold,
+                allow,
+                deny,
+            }),
+        ),
+        Opt::Age {
+            build_constraints,
+            days,
+        } => command::age::age(&build_constraints, days),
+        Opt::AuditBounds { build_constraints } => {
+            command::audit_bounds::audit_bounds(&build_constraints)
+        }
         Opt::Affected {
             build_constraints,
             older,
             newer,
         } => command::affected::affected(&build_constraints, &older, &newer),
         Opt::Clear { build_constraints } => command::clear(&build_constraints),
+        Opt::Culprits { build_constraints } => command::culprits::culprits(&build_constraints),
+        Opt::BcDiff {
+            build_constraints,
+            old,
+            new,
+            mode,
+        } => command::bc_diff::bc_diff(&build_constraints, &old, &new, mode),
+        Opt::Blame {
+            build_constraints,
+            package,
+        } => command::blame::blame(&build_constraints, &package),
         Opt::DiffSnapshot {
             older,
             newer,
             mode,
             ignore_file,
         } => command::diff_snapshot::diff_snapshot(&older, &newer, mode, ignore_file.as_deref()),
-        Opt::Disabled { build_constraints } => command::disabled::disabled(&build_constraints),
-        Opt::Grandfather { build_constraints } => {
-            command::grandfather::grandfather(&build_constraints)
-        }
-        Opt::Maintainers { build_constraints } => {
-            command::maintainers::maintainers(&build_constraints)
+        Opt::Disabled {
+            build_constraints,
+            tree,
+            graph,
+        } => command::disabled::disabled(&build_constraints, tree.as_deref(), graph),
+        Opt::Fmt {
+            build_constraints,
+            check,
+        } => command::fmt::fmt(&build_constraints, check),
+        Opt::Grandfather {
+            build_constraints,
+            write,
+            prune,
+            snapshot,
+        } => {
+            command::grandfather::grandfather(&build_constraints, write, prune, snapshot.as_deref())
         }
-        Opt::Multiple { build_constraints } => command::multiple::multiple(&build_constraints),
+        Opt::Issues {
+            build_constraints,
+            out_dir,
+        } => command::issues::issues(&build_constraints, &out_dir),
+        Opt::IssuesIndex {
+            build_constraints,
+            mode,
+        } => command::issues_index::issues_index(&build_constraints, mode),
+        Opt::Lint {
+            build_constraints,
+            fix,
+            deny_warnings,
+        } => command::lint::lint(&build_constraints, fix, deny_warnings),
+        Opt::Maintainer {
+            build_constraints,
+            query,
+            all,
+            out_dir,
+            snapshot,
+            no_hackage,
+        } => command::maintainer::maintainer(
+            &build_constraints,
+            query.as_deref(),
+            all,
+            out_dir.as_deref(),
+            snapshot.as_deref(),
+            no_hackage,
+        ),
+        Opt::Maintainers {
+            build_constraints,
+            fix,
+        } => command::maintainers::maintainers(&build_constraints, fix),
+        Opt::Move {
+            build_constraints,
+            to,
+            packages,
+        } => command::move_packages::move_packages(&build_constraints, &packages, &to),
+        Opt::Multiple {
+            build_constraints,
+            resolve,
+        } => command::multiple::multiple(&build_constraints, resolve),
         Opt::Outdated {
             build_constraints,
             ignore_file,
@@ -141,5 +501,30 @@ fn main() {
             &build_constraints,
             &package,
         ),
+        Opt::Released { build_constraints } => command::released::released(&build_constraints),
+        Opt::Site {
+            build_constraints,
+            out,
+            snapshot,
+            no_hackage,
+        } => command::site::site(&build_constraints, &out, snapshot.as_deref(), no_hackage),
+        Opt::Stale {
+            build_constraints,
+            snapshot,
+            remove,
+        } => command::stale::stale(&build_constraints, &snapshot, remove),
+        Opt::Stats {
+            build_constraints,
+            history,
+            range,
+            mode,
+        } => command::stats::stats(&build_constraints, history, range.as_deref(), mode),
+        Opt::TestBounds { build_constraints } => {
+            command::test_bounds::test_bounds(&build_constraints)
+        }
+        Opt::Why {
+            build_constraints,
+            package,
+        } => command::why::why(&build_constraints, &package),
     }
 }
@@ -4,8 +4,7 @@ pub use std::ffi::OsStr;
 pub use std::fmt;
 pub use std::path::{Path, PathBuf};
 
-pub use anyhow::{anyhow, Context};
+pub use anyhow::Context;
 pub use lazy_regex::regex;
-pub use regex::Regex;
 
 pub(crate) use crate::util::fs;
@@ -0,0 +1,381 @@
+//! Line based access to the sections under `packages:`, for commands
+//! that edit build-constraints.yaml in place and need to keep comments
+//! and layout intact.
+
+use crate::prelude::*;
+
+use std::ops::Range;
+
+use crate::regex::*;
+use crate::types::Package;
+
+#[derive(Debug)]
+pub(crate) struct Section {
+    pub(crate) name: String,
+    /// Index of the `    "name":` line.
+    pub(crate) header: usize,
+    pub(crate) entries: Vec<Entry>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Entry {
+    /// First line of the comments directly above the entry, or `line`
+    /// if there are none.
+    pub(crate) start: usize,
+    pub(crate) line: usize,
+    pub(crate) package: Package,
+}
+
+impl Section {
+    /// Index of the line after the last entry, or after the header for
+    /// an empty section.
+    pub(crate) fn end(&self) -> usize {
+        self.entries
+            .last()
+            .map(|e| e.line + 1)
+            .unwrap_or(self.header + 1)
+    }
+}
+
+pub(crate) fn read(build_constraints: &Path) -> Vec<String> {
+    fs::read_lines(build_constraints)
+        .map(|l| l.unwrap())
+        .collect()
+}
+
+pub(crate) fn sections(lines: &[String]) -> Vec<Section> {
+    let mut sections: Vec<Section> = vec![];
+    let mut in_packages = false;
+    let mut comments_start: Option<usize> = None;
+    for (i, line) in lines.iter().enumerate() {
+        if !in_packages {
+            in_packages = line == "packages:";
+            continue;
+        }
+        let comment_start = comments_start.take();
+        if line == "# end of packages" || regex!(r#"^[^ #]"#).is_match(line) {
+            break;
+        }
+        if let Some(name) = parse_header(line) {
+            sections.push(Section {
+                name,
+                header: i,
+                entries: vec![],
+            });
+        } else if let Some(package) = parse_entry(line) {
+            if let Some(section) = sections.last_mut() {
+                section.entries.push(Entry {
+                    start: comment_start.unwrap_or(i),
+                    line: i,
+                    package,
+                });
+            }
+        } else if regex!(r#"^ +#"#).is_match(line) && !sections.is_empty() {
+            comments_start = Some(comment_start.unwrap_or(i));
+        }
+    }
+    sections
+}
+
+pub(crate) fn parse_header(line: &str) -> Option<String> {
+    Captures::new(regex!(r#"^    "([^"]+)": *(?:\[\])? *$"#), line)
+        .ok()
+        .map(|cap| cap.get(1).unwrap())
+}
+
+pub(crate) fn parse_entry(line: &str) -> Option<Package> {
+    Captures::new(regex!(r#"^ +- +([^ #]+)"#), line)
+        .ok()
+        .map(|cap| cap.get(1).unwrap())
+}
+
+/// An entry line split into package, bound and comment, e.g.
+/// `- foo < 2 # reason` into `(foo, "< 2", "reason")`.
+pub(crate) fn split_entry(line: &str) -> Option<(Package, String, String)> {
+    let cap = Captures::new(
+        regex!(r#"^ *- +([^ #]+) *([^#]*?) *(?:#\s*(.*?))?\s*$"#),
+        line,
+    )
+    .ok()?;
+    Some((
+        cap.get(1).unwrap(),
+        cap.get(2).unwrap_or_default(),
+        cap.get(3).unwrap_or_default(),
+    ))
+}
+
+/// Case insensitive ordering of entry lines, used by `fmt`.
+pub(crate) fn sort_key(line: &str) -> (String, String) {
+    let line = line.trim();
+    (line.to_lowercase(), line.to_owned())
+}
+
+/// Sorts the entry lines of a block by `key`, keeping comment lines
+/// together with the entry below them. Comments after the last entry
+/// stay at the end.
+pub(crate) fn sort_entries<K: Ord>(lines: &mut [String], key: impl Fn(&str) -> K) {
+    let mut groups: Vec<Vec<String>> = vec![];
+    let mut pending: Vec<String> = vec![];
+    for line in lines.iter() {
+        pending.push(line.clone());
+        if !is_comment(line) {
+            groups.push(std::mem::take(&mut pending));
+        }
+    }
+    groups.sort_by_key(|g| key(g.last().unwrap()));
+    for (i, line) in groups.into_iter().flatten().chain(pending).enumerate() {
+        lines[i] = line;
+    }
+}
+
+/// The index of the first entry line that sorts before the entry above
+/// it by `key`, ignoring comment lines.
+pub(crate) fn first_unsorted<K: Ord>(lines: &[String], key: impl Fn(&str) -> K) -> Option<usize> {
+    let entries: Vec<usize> = (0..lines.len())
+        .filter(|&i| !is_comment(&lines[i]))
+        .collect();
+    entries
+        .windows(2)
+        .find(|w| key(&lines[w[0]]) > key(&lines[w[1]]))
+        .map(|w| w[1])
+}
+
+fn is_comment(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
+
+fn find<'a>(sections: &'a [Section], name: &str) -> &'a Section {
+    sections
+        .iter()
+        .find(|s| s.name == name)
+        .unwrap_or_else(|| panic!("Could not find section {name:?}"))
+}
+
+/// Inserts `entry` (e.g. `foo < 0 # comment`) before the first entry in
+/// the section that sorts after `package`.
+pub(crate) fn insert_sorted(
+    lines: &mut Vec<String>,
+    section: &str,
+    package: &Package,
+    entry: &str,
+) {
+    insert_block_sorted(lines, section, package, vec![format!("- {entry}")]);
+}
+
+/// Like `insert_sorted`, but for an entry together with the comment
+/// lines above it. Lines are reindented to match the section.
+pub(crate) fn insert_block_sorted(
+    lines: &mut Vec<String>,
+    section: &str,
+    package: &Package,
+    block: Vec<String>,
+) {
+    let sections = sections(lines);
+    let section = find(&sections, section);
+    let indent = section
+        .entries
+        .first()
+        .map(|e| {
+            let line = &lines[e.line];
+            line[..line.len() - line.trim_start().len()].to_owned()
+        })
+        .unwrap_or_else(|| "        ".to_owned());
+    let pos = section
+        .entries
+        .iter()
+        .find(|e| e.package > *package)
+        .map(|e| e.start)
+        .unwrap_or_else(|| section.end());
+    lines[section.header] = format!("    \"{}\":", section.name);
+    for (i, line) in block.into_iter().enumerate() {
+        lines.insert(pos + i, format!("{indent}{}", line.trim_start()));
+    }
+}
+
+/// Removes all entries for the given packages from the section,
+/// together with the comments directly above them, and returns the
+/// removed blocks.
+pub(crate) fn remove(
+    lines: &mut Vec<String>,
+    section: &str,
+    packages: &BTreeSet<Package>,
+) -> Vec<(Package, Vec<String>)> {
+    let sections = sections(lines);
+    let section = find(&sections, section);
+    let remove: Vec<&Entry> = section
+        .entries
+        .iter()
+        .filter(|e| packages.contains(&e.package))
+        .collect();
+    if remove.len() == section.entries.len() && !remove.is_empty() {
+        lines[section.header] = format!("    \"{}\": []", section.name);
+    }
+    let mut removed: Vec<(Package, Vec<String>)> = remove
+        .iter()
+        .rev()
+        .map(|e| (e.package.clone(), lines.drain(e.start..=e.line).collect()))
+        .collect();
+    removed.reverse();
+    removed
+}
+
+/// Removes a section header and everything up to its last entry, along
+/// with a following blank line.
+pub(crate) fn remove_section(lines: &mut Vec<String>, section: &str) {
+    let sections = sections(lines);
+    let section = find(&sections, section);
+    let mut end = section.end();
+    if lines.get(end).is_some_and(|l| l.trim().is_empty()) {
+        end += 1;
+    }
+    lines.drain(section.header..end);
+}
+
+/// The lines belonging to a top level key such as `skipped-tests`,
+/// excluding the key itself.
+pub(crate) fn top_level(lines: &[String], key: &str) -> Option<Range<usize>> {
+    let start = lines.iter().position(|l| {
+        l.strip_prefix(key)
+            .is_some_and(|rest| rest.starts_with(':'))
+    })? + 1;
+    let end = lines[start..]
+        .iter()
+        .position(|l| regex!(r#"^[^ #-]"#).is_match(l))
+        .map_or(lines.len(), |i| i + start);
+    Some(start..end)
+}
+
+/// The `- package` items of a top level list such as `skipped-tests`.
+pub(crate) fn list_items(lines: &[String], key: &str) -> Vec<(usize, Package)> {
+    let Some(range) = top_level(lines, key) else {
+        return vec![];
+    };
+    range
+        .filter_map(|i| {
+            let cap = Captures::new(regex!(r#"^ *- +([^ #]+)"#), &lines[i]).ok()?;
+            Some((i, cap.get(1).unwrap()))
+        })
+        .collect()
+}
+
+#[test]
+fn test_sections() {
+    let lines = read(&PathBuf::from("test/build-constraints.yaml"));
+    let sections = sections(&lines);
+    assert_eq!(sections[0].name, "Xy Ren <xy.r@outlook.com> @re-xyr");
+    assert_eq!(
+        sections[0]
+            .entries
+            .iter()
+            .map(|e| e.package.to_string())
+            .collect::<Vec<_>>(),
+        vec!["cleff", "rec-smallarray"]
+    );
+    assert!(sections.iter().any(
+        |s| s.name == "Unmaintained packages with compilation failures" && s.entries.is_empty()
+    ));
+    assert_eq!(sections.last().unwrap().name, "Stackage upper bounds");
+}
+
+#[test]
+fn test_insert_remove() {
+    let mut lines: Vec<String> = [
+        "packages:",
+        "    \"A\":",
+        "        - bar",
+        "        - foo # comment",
+        "",
+        "    \"B\": []",
+        "# end of packages",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    insert_sorted(&mut lines, "A", &"baz".into(), "baz");
+    insert_sorted(&mut lines, "A", &"zoo".into(), "zoo < 0");
+    insert_sorted(&mut lines, "B", &"qux".into(), "qux");
+    assert_eq!(
+        lines,
+        vec![
+            "packages:",
+            "    \"A\":",
+            "        - bar",
+            "        - baz",
+            "        - foo # comment",
+            "        - zoo < 0",
+            "",
+            "    \"B\":",
+            "        - qux",
+            "# end of packages",
+        ]
+    );
+    let removed = remove(&mut lines, "B", &BTreeSet::from(["qux".into()]));
+    assert_eq!(
+        removed,
+        vec![("qux".into(), vec!["        - qux".to_owned()])]
+    );
+    assert_eq!(lines[7], "    \"B\": []");
+}
+
+#[test]
+fn test_move_with_comments() {
+    let mut lines: Vec<String> = [
+        "packages:",
+        "    \"A\":",
+        "        - bar",
+        "        # about foo",
+        "        - foo # comment",
+        "",
+        "    \"B\":",
+        "      - baz",
+        "# end of packages",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    let removed = remove(&mut lines, "A", &BTreeSet::from(["foo".into()]));
+    for (package, block) in removed {
+        insert_block_sorted(&mut lines, "B", &package, block);
+    }
+    remove_section(&mut lines, "A");
+    assert_eq!(
+        lines,
+        vec![
+            "packages:",
+            "    \"B\":",
+            "      - baz",
+            "      # about foo",
+            "      - foo # comment",
+            "# end of packages",
+        ]
+    );
+}
+
+#[test]
+fn test_sort_entries() {
+    let mut lines: Vec<String> = [
+        "        - b < 0",
+        "        # a comment on Zlib",
+        "        - Zlib < 0",
+        "        - a < 0",
+        "        # trailing",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    assert_eq!(first_unsorted(&lines, str::to_owned), Some(2));
+    assert_eq!(first_unsorted(&lines, sort_key), Some(3));
+    sort_entries(&mut lines, sort_key);
+    assert_eq!(
+        lines,
+        vec![
+            "        - a < 0",
+            "        - b < 0",
+            "        # a comment on Zlib",
+            "        - Zlib < 0",
+            "        # trailing",
+        ]
+    );
+    assert_eq!(first_unsorted(&lines, sort_key), None);
+}
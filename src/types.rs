@@ -73,10 +73,7 @@ impl TryFrom<&str> for Version {
     }
 }
 
-#[derive(Clone, PartialOrd, Ord, PartialEq, Eq, Debug, Hash)]
-pub struct Revision(pub usize);
-
-#[derive(PartialEq, Eq, Debug, Clone, Hash)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Hash)]
 pub struct VersionedPackage {
     pub package: Package,
     pub version: Version,
@@ -104,3 +101,45 @@ impl TryFrom<String> for VersionedPackage {
         })
     }
 }
+
+/// Appended to generated bounds comments to record when, and against
+/// which target, the failure was seen, e.g. `[nightly-2023-01-01 2023-01-02]`.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Tag {
+    pub target: Option<String>,
+    pub date: chrono::NaiveDate,
+}
+
+impl Tag {
+    pub fn today(target: Option<String>) -> Tag {
+        Tag {
+            target,
+            date: chrono::Utc::now().date_naive(),
+        }
+    }
+
+    /// Splits a trailing tag off of a line, leaving the line untouched if
+    /// it has none.
+    pub fn split(s: &str) -> (&str, Option<Tag>) {
+        let Some(cap) = regex!(r#" *\[(?:([^ \]]+) )?(\d{4}-\d{2}-\d{2})\] *$"#).captures(s) else {
+            return (s, None);
+        };
+        let Ok(date) = chrono::NaiveDate::parse_from_str(&cap[2], "%Y-%m-%d") else {
+            return (s, None);
+        };
+        let tag = Tag {
+            target: cap.get(1).map(|m| m.as_str().to_owned()),
+            date,
+        };
+        (&s[..cap.get(0).unwrap().start()], Some(tag))
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.target {
+            Some(target) => write!(f, "[{target} {}]", self.date),
+            None => write!(f, "[{}]", self.date),
+        }
+    }
+}
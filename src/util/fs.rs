@@ -2,7 +2,7 @@ use anyhow::{Context, Result};
 use std::fmt;
 use std::fs::File;
 pub use std::fs::ReadDir;
-use std::io::{BufRead, BufReader, Lines};
+use std::io::{BufRead, BufReader, LineWriter, Lines, Write};
 use std::path::Path;
 
 pub(crate) use std::fs::metadata;
@@ -20,3 +20,20 @@ where
 pub fn read_dir<P: AsRef<Path> + fmt::Debug>(path: P) -> Result<ReadDir> {
     std::fs::read_dir(&path).with_context(|| format!("Error reading directory {path:?}"))
 }
+
+pub(crate) fn create_dir_all<P: AsRef<Path> + fmt::Debug>(path: P) -> Result<()> {
+    std::fs::create_dir_all(&path).with_context(|| format!("Error creating directory {path:?}"))
+}
+
+pub(crate) fn write_lines<P>(filename: P, lines: impl IntoIterator<Item = String>)
+where
+    P: AsRef<Path> + fmt::Debug,
+{
+    let file = File::create(&filename)
+        .unwrap_or_else(|e| panic!("Could not create {filename:?}, error: {e}"));
+    let mut file = LineWriter::new(file);
+    for line in lines {
+        file.write_all((line + "\n").as_bytes()).unwrap();
+    }
+    file.flush().unwrap();
+}